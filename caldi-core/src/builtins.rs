@@ -0,0 +1,307 @@
+use std::cmp::Ordering;
+
+use super::{
+    complex::Complex,
+    matrix::Matrix,
+    random,
+    session::Scope,
+    symbolic::Term,
+    value::{OperandError, Result, Value},
+};
+
+/// The name of every built in function.
+pub const NAMES: &[&str] = &[
+    "abs",
+    "sqrt",
+    "square root",
+    "arg",
+    "conj",
+    "re",
+    "im",
+    "transpose",
+    "det",
+    "determinant",
+    "inv",
+    "inverse",
+    "solve",
+    "rand",
+    "random",
+    "randint",
+    "sum",
+    "total",
+    "product",
+    "prod",
+    "integrate",
+    "integral",
+    "mean",
+    "average",
+    "avg",
+    "median",
+    "mode",
+    "min",
+    "minimum",
+    "smallest",
+    "max",
+    "maximum",
+    "largest",
+    "variance",
+    "var",
+    "stddev",
+    "std",
+    "standard deviation",
+];
+
+/// Calls the built in function called `name`, if there is one.
+pub fn call(name: &str, args: Vec<Value>, scope: &Scope) -> Option<Result<Value>> {
+    let settings = scope.settings();
+
+    let function: Option<fn(Value) -> Result<Value>> = match name {
+        "abs" => Some(abs),
+        "sqrt" | "square root" if settings.complex => Some(complex_sqrt),
+        "sqrt" | "square root" => Some(sqrt),
+        "arg" => Some(arg),
+        "conj" => Some(conj),
+        "re" => Some(re),
+        "im" => Some(im),
+        "transpose" => Some(transpose),
+        "det" | "determinant" => Some(determinant),
+        "inv" | "inverse" => Some(inverse),
+        _ => None,
+    };
+
+    if let Some(function) = function {
+        return Some(exactly(name, args).and_then(|[arg]| function(arg)));
+    }
+
+    if name == "solve" {
+        return Some(exactly(name, args).and_then(|[a, b]| solve(a, b)));
+    }
+
+    if matches!(name, "rand" | "random") {
+        return Some(exactly(name, args).map(|[]| scope.rng().f64().into()));
+    }
+
+    if name == "randint" {
+        return Some(exactly(name, args).and_then(|[from, to]| {
+            random::integer(&mut scope.rng(), from.into_integer()?, to.into_integer()?)
+                .map(Value::from)
+        }));
+    }
+
+    let aggregate: fn(Vec<Value>) -> Result<Value> = match name {
+        "sum" | "total" => sum,
+        "product" | "prod" => product,
+        "mean" | "average" | "avg" => mean,
+        "median" => median,
+        "mode" => mode,
+        "min" | "minimum" | "smallest" => min,
+        "max" | "maximum" | "largest" => max,
+        "variance" | "var" => variance,
+        "stddev" | "std" | "standard deviation" => stddev,
+        _ => return None,
+    };
+
+    Some(numbers(args).and_then(aggregate))
+}
+
+fn exactly<const N: usize>(name: &str, args: Vec<Value>) -> Result<[Value; N]> {
+    <[Value; N]>::try_from(args).map_err(|args| OperandError::WrongArgumentCount {
+        name: name.to_string(),
+        expected: N,
+        found: args.len(),
+    })
+}
+
+/// Aggregates take either a single list, or the numbers themselves as arguments.
+fn numbers(args: Vec<Value>) -> Result<Vec<Value>> {
+    let numbers = match <[Value; 1]>::try_from(args) {
+        Ok([Value::List(items)]) => items,
+        Ok([arg]) => vec![arg],
+        Err(args) => args,
+    };
+
+    if let Some(other) = numbers.iter().find(|n| !n.is_number()) {
+        return Err(OperandError::expected("number", other));
+    }
+
+    if numbers.is_empty() {
+        return Err(OperandError::EmptyList);
+    }
+
+    Ok(numbers)
+}
+
+fn sum(numbers: Vec<Value>) -> Result<Value> {
+    numbers
+        .into_iter()
+        .try_fold(Value::Integer(0), |total, n| total + n)
+}
+
+fn product(numbers: Vec<Value>) -> Result<Value> {
+    numbers
+        .into_iter()
+        .try_fold(Value::Integer(1), |total, n| total * n)
+}
+
+fn mean(numbers: Vec<Value>) -> Result<Value> {
+    let count = numbers.len() as isize;
+    sum(numbers)? / Value::Integer(count)
+}
+
+fn median(mut numbers: Vec<Value>) -> Result<Value> {
+    sort(&mut numbers);
+
+    let middle = numbers.len() / 2;
+
+    if numbers.len() % 2 == 1 {
+        return Ok(numbers.swap_remove(middle));
+    }
+
+    let upper = numbers.swap_remove(middle);
+    let lower = numbers.swap_remove(middle - 1);
+
+    (lower + upper)? / Value::Integer(2)
+}
+
+/// The most common number, or the smallest of them when there is a tie.
+fn mode(mut numbers: Vec<Value>) -> Result<Value> {
+    sort(&mut numbers);
+
+    let mut best = (0, 0);
+    let mut run = (0, 0);
+
+    for i in 0..numbers.len() {
+        if i > 0 && numbers[i].equals(&numbers[i - 1])? {
+            run.1 += 1;
+        } else {
+            run = (i, 1);
+        }
+
+        if run.1 > best.1 {
+            best = run;
+        }
+    }
+
+    Ok(numbers.swap_remove(best.0))
+}
+
+fn min(mut numbers: Vec<Value>) -> Result<Value> {
+    sort(&mut numbers);
+    Ok(numbers.swap_remove(0))
+}
+
+fn max(mut numbers: Vec<Value>) -> Result<Value> {
+    sort(&mut numbers);
+    Ok(numbers
+        .pop()
+        .expect("aggregates are never given an empty list"))
+}
+
+fn variance(numbers: Vec<Value>) -> Result<Value> {
+    Ok(population_variance(numbers).into())
+}
+
+fn stddev(numbers: Vec<Value>) -> Result<Value> {
+    Ok(population_variance(numbers).sqrt().into())
+}
+
+/// The mean of the squared deviations from the mean.
+fn population_variance(numbers: Vec<Value>) -> f64 {
+    let count = numbers.len() as f64;
+    let numbers = numbers.iter().map(Value::as_f64).collect::<Vec<_>>();
+
+    let mean = numbers.iter().sum::<f64>() / count;
+    let squares = numbers.iter().map(|n| (n - mean).powi(2)).sum::<f64>();
+
+    squares / count
+}
+
+/// How far from zero it is, where zero is in the same units for amounts that have them.
+fn abs(value: Value) -> Result<Value> {
+    if let Value::Complex(c) = value {
+        return Ok(c.modulus().into());
+    }
+
+    let zero = (value.clone() * Value::Integer(0))?;
+
+    if value.compare(&zero)? == Some(Ordering::Less) {
+        -value
+    } else {
+        Ok(value)
+    }
+}
+
+fn sqrt(value: Value) -> Result<Value> {
+    match value {
+        n if n.is_number() => Ok(n.as_f64().sqrt().into()),
+        Value::Expression(t) => Ok(t.pow(Term::Number(0.5)).into()),
+        other => Err(OperandError::expected("number", &other)),
+    }
+}
+
+/// The square root that's an imaginary number for negative numbers, instead of NaN.
+fn complex_sqrt(value: Value) -> Result<Value> {
+    Ok(scalar(value)?.sqrt().into())
+}
+
+fn arg(value: Value) -> Result<Value> {
+    Ok(scalar(value)?.arg().into())
+}
+
+fn conj(value: Value) -> Result<Value> {
+    Ok(scalar(value)?.conj().into())
+}
+
+fn re(value: Value) -> Result<Value> {
+    Ok(scalar(value)?.re.into())
+}
+
+fn im(value: Value) -> Result<Value> {
+    Ok(scalar(value)?.im.into())
+}
+
+/// Takes a real number as a complex one with no imaginary part.
+fn scalar(value: Value) -> Result<Complex> {
+    match value {
+        Value::Complex(c) => Ok(c),
+        n if n.is_number() => Ok(n.as_complex()),
+        other => Err(OperandError::expected("number", &other)),
+    }
+}
+
+fn matrix(value: Value) -> Result<Matrix> {
+    match value {
+        Value::Matrix(m) => Ok(m),
+        other => Err(OperandError::expected("matrix", &other)),
+    }
+}
+
+fn transpose(value: Value) -> Result<Value> {
+    Ok(matrix(value)?.transpose().into())
+}
+
+fn determinant(value: Value) -> Result<Value> {
+    Ok(matrix(value)?.determinant()?.into())
+}
+
+fn inverse(value: Value) -> Result<Value> {
+    Ok(matrix(value)?.inverse()?.into())
+}
+
+/// Solves the linear system `a * x = b`, where `b` is either a list or a matrix.
+fn solve(a: Value, b: Value) -> Result<Value> {
+    let a = matrix(a)?;
+
+    match b {
+        Value::List(items) => match Matrix::column(&items) {
+            Some(column) => Ok(a.solve(&column)?.into_list()),
+            None => Err(OperandError::expected("numbers", &Value::List(items))),
+        },
+        b => Ok(a.solve(&matrix(b)?)?.into()),
+    }
+}
+
+fn sort(numbers: &mut [Value]) {
+    // every value has been checked to be a number, so comparing can only fail on NaN
+    numbers.sort_by(|a, b| a.compare(b).ok().flatten().unwrap_or(Ordering::Equal));
+}
@@ -0,0 +1,166 @@
+use std::{cmp::Ordering, fmt::Display};
+
+use chrono::{Local, NaiveDate, NaiveTime, TimeDelta};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    units::Quantity,
+    value::{OperandError, Result, Value},
+};
+
+const MONTHS: [&str; 12] = [
+    "january",
+    "february",
+    "march",
+    "april",
+    "may",
+    "june",
+    "july",
+    "august",
+    "september",
+    "october",
+    "november",
+    "december",
+];
+
+/// The number of the month with this name, or the start of it (e.g. "Sept").
+pub fn month(name: &str) -> Option<u32> {
+    let name = name.to_lowercase();
+
+    if name.len() < 3 {
+        return None;
+    }
+
+    MONTHS
+        .iter()
+        .position(|month| month.starts_with(&name))
+        .map(|i| i as u32 + 1)
+}
+
+pub fn today() -> NaiveDate {
+    Local::now().date_naive()
+}
+
+/// Dates and times that go by a name, like "today" or "noon".
+pub fn named(name: &str) -> Option<Value> {
+    let value = match name.to_lowercase().as_str() {
+        "today" => today().into(),
+        "tomorrow" => today().succ_opt()?.into(),
+        "yesterday" => today().pred_opt()?.into(),
+        "now" => Local::now().time().into(),
+        "noon" | "midday" => NaiveTime::from_hms_opt(12, 0, 0)?.into(),
+        "midnight" => NaiveTime::MIN.into(),
+        _ => return None,
+    };
+
+    Some(value)
+}
+
+/// A span of time, that gets said in days, hours, minutes and seconds.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Duration {
+    seconds: f64,
+}
+
+impl Duration {
+    pub fn from_seconds(seconds: f64) -> Self {
+        Self { seconds }
+    }
+
+    pub fn seconds(&self) -> f64 {
+        self.seconds
+    }
+
+    pub fn into_quantity(self) -> Quantity {
+        Quantity::seconds(self.seconds)
+    }
+
+    pub fn scale(self, factor: f64) -> Duration {
+        Duration::from_seconds(self.seconds * factor)
+    }
+
+    pub fn compare(&self, other: &Duration) -> Option<Ordering> {
+        self.seconds.partial_cmp(&other.seconds)
+    }
+
+    fn delta(&self) -> Result<TimeDelta> {
+        let millis = (self.seconds * 1000.0).round();
+
+        if !millis.is_finite() || millis.abs() >= i64::MAX as f64 {
+            return Err(OperandError::Overflow);
+        }
+
+        TimeDelta::try_milliseconds(millis as i64).ok_or(OperandError::Overflow)
+    }
+
+    /// Moves a date by the whole days in this duration.
+    pub fn after_date(&self, date: NaiveDate) -> Result<NaiveDate> {
+        let days = TimeDelta::try_days(self.delta()?.num_days()).ok_or(OperandError::Overflow)?;
+        date.checked_add_signed(days).ok_or(OperandError::Overflow)
+    }
+
+    /// Moves a time of day, going around the clock past midnight.
+    pub fn after_time(&self, time: NaiveTime) -> Result<NaiveTime> {
+        Ok(time.overflowing_add_signed(self.delta()?).0)
+    }
+}
+
+impl Display for Duration {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.seconds < 0.0 {
+            write!(f, "minus ")?;
+        }
+
+        let total = (self.seconds.abs() * 1000.0).round() / 1000.0;
+        let whole = total.trunc() as u64;
+
+        let parts = [
+            (whole / 86400, "day"),
+            (whole % 86400 / 3600, "hour"),
+            (whole % 3600 / 60, "minute"),
+        ];
+
+        let mut said = false;
+
+        for (amount, name) in parts.into_iter().filter(|(amount, _)| *amount > 0) {
+            if said {
+                write!(f, " ")?;
+            }
+            write!(f, "{amount} {name}{}", if amount == 1 { "" } else { "s" })?;
+            said = true;
+        }
+
+        let seconds = total - (whole - whole % 60) as f64;
+
+        if seconds > 0.0 || !said {
+            if said {
+                write!(f, " ")?;
+            }
+            write!(
+                f,
+                "{seconds} second{}",
+                if seconds == 1.0 { "" } else { "s" }
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+impl From<Duration> for Value {
+    fn from(value: Duration) -> Self {
+        Self::Duration(value)
+    }
+}
+
+impl From<NaiveDate> for Value {
+    fn from(value: NaiveDate) -> Self {
+        Self::Date(value)
+    }
+}
+
+impl From<NaiveTime> for Value {
+    fn from(value: NaiveTime) -> Self {
+        Self::Time(value)
+    }
+}
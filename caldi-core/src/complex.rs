@@ -0,0 +1,139 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+use super::value::Value;
+
+/// A number with an imaginary part, as used for things like impedances.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Complex {
+    pub re: f64,
+    pub im: f64,
+}
+
+impl Complex {
+    pub const I: Complex = Complex { re: 0.0, im: 1.0 };
+
+    pub fn new(re: f64, im: f64) -> Self {
+        Self { re, im }
+    }
+
+    pub fn from_polar(modulus: f64, arg: f64) -> Self {
+        Self::new(modulus * arg.cos(), modulus * arg.sin())
+    }
+
+    /// The distance from zero, which is what `abs` gives.
+    pub fn modulus(&self) -> f64 {
+        self.re.hypot(self.im)
+    }
+
+    /// The angle from the positive real axis, in radians.
+    pub fn arg(&self) -> f64 {
+        self.im.atan2(self.re)
+    }
+
+    pub fn conj(self) -> Self {
+        Self::new(self.re, -self.im)
+    }
+
+    pub(crate) fn add(self, other: Complex) -> Self {
+        Self::new(self.re + other.re, self.im + other.im)
+    }
+
+    pub(crate) fn sub(self, other: Complex) -> Self {
+        Self::new(self.re - other.re, self.im - other.im)
+    }
+
+    pub(crate) fn mul(self, other: Complex) -> Self {
+        Self::new(
+            self.re * other.re - self.im * other.im,
+            self.re * other.im + self.im * other.re,
+        )
+    }
+
+    pub(crate) fn div(self, other: Complex) -> Self {
+        let denominator = other.re * other.re + other.im * other.im;
+
+        Self::new(
+            (self.re * other.re + self.im * other.im) / denominator,
+            (self.im * other.re - self.re * other.im) / denominator,
+        )
+    }
+
+    pub(crate) fn neg(self) -> Self {
+        Self::new(-self.re, -self.im)
+    }
+
+    /// The principal square root, worked out without going through angles so that
+    /// the square root of -4 is exactly 2i.
+    pub fn sqrt(self) -> Self {
+        let modulus = self.modulus();
+        let re = ((modulus + self.re) / 2.0).sqrt();
+        let im = ((modulus - self.re) / 2.0).sqrt();
+
+        Self::new(re, if self.im < 0.0 { -im } else { im })
+    }
+
+    pub fn powi(self, n: i32) -> Self {
+        // multiplying it out keeps whole numbers whole, unlike going through polar form
+        let mut result = Self::new(1.0, 0.0);
+        let mut base = if n < 0 {
+            Self::new(1.0, 0.0).div(self)
+        } else {
+            self
+        };
+        let mut n = n.unsigned_abs();
+
+        while n > 0 {
+            if n & 1 == 1 {
+                result = result.mul(base);
+            }
+            base = base.mul(base);
+            n >>= 1;
+        }
+
+        result
+    }
+
+    pub fn powc(self, exponent: Complex) -> Self {
+        if self.re == 0.0 && self.im == 0.0 {
+            return self;
+        }
+
+        // z^w = e^(w ln z)
+        let ln = Self::new(self.modulus().ln(), self.arg());
+        let power = exponent.mul(ln);
+
+        Self::from_polar(power.re.exp(), power.im)
+    }
+}
+
+impl Display for Complex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let sign = if self.im < 0.0 { "-" } else { "+" };
+
+        // a coefficient of one goes without saying, like in 3 + i
+        let im = if self.im.abs() == 1.0 {
+            String::new()
+        } else {
+            self.im.abs().to_string()
+        };
+
+        if self.re == 0.0 {
+            return write!(f, "{}{im}i", if self.im < 0.0 { "-" } else { "" });
+        }
+
+        write!(f, "{} {sign} {im}i", self.re)
+    }
+}
+
+impl From<Complex> for Value {
+    /// Complex numbers that have come out real are just numbers again.
+    fn from(value: Complex) -> Self {
+        if value.im == 0.0 {
+            return Self::Float(value.re);
+        }
+
+        Self::Complex(value)
+    }
+}
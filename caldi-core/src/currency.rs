@@ -0,0 +1,230 @@
+use std::{cmp::Ordering, collections::HashMap, fmt::Display, path::Path};
+
+use anyhow::Context;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use super::value::{OperandError, Result, Value};
+
+#[derive(Debug, PartialEq)]
+pub struct Currency {
+    /// ISO 4217 code, which is also what the rate file is keyed by.
+    pub code: &'static str,
+    /// Spelled out names, including plurals.
+    pub names: &'static [&'static str],
+    /// Digits shown after the decimal point.
+    pub decimals: usize,
+}
+
+macro_rules! currencies {
+    ($($code:literal [$($name:literal),*] $decimals:literal),* $(,)?) => {
+        &[$(Currency {
+            code: $code,
+            names: &[$($name),*],
+            decimals: $decimals,
+        }),*]
+    };
+}
+
+static CURRENCIES: &[Currency] = currencies![
+    "USD" ["$", "dollar", "dollars", "buck", "bucks", "us dollar", "us dollars"] 2,
+    "EUR" ["€", "euro", "euros"] 2,
+    "GBP" ["£", "british pound", "british pounds", "pound sterling", "pounds sterling", "quid"] 2,
+    "JPY" ["¥", "yen"] 0,
+    "CNY" ["yuan", "renminbi"] 2,
+    "INR" ["₹", "rupee", "rupees"] 2,
+    "CAD" ["canadian dollar", "canadian dollars"] 2,
+    "AUD" ["australian dollar", "australian dollars"] 2,
+    "CHF" ["swiss franc", "swiss francs", "franc", "francs"] 2,
+    "MXN" ["peso", "pesos", "mexican peso", "mexican pesos"] 2,
+];
+
+/// Currencies are saved as their code, and looked up again when they're read back.
+impl Serialize for Currency {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.code)
+    }
+}
+
+impl<'de> Deserialize<'de> for &'static Currency {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let code = String::deserialize(deserializer)?;
+        CURRENCIES
+            .iter()
+            .find(|currency| currency.code == code)
+            .ok_or_else(|| de::Error::custom(format!("unknown currency {code}")))
+    }
+}
+
+/// Every currency's code and names.
+pub fn names() -> impl Iterator<Item = &'static str> {
+    CURRENCIES
+        .iter()
+        .flat_map(|currency| std::iter::once(currency.code).chain(currency.names.iter().copied()))
+}
+
+/// Finds a currency by its code, or one of its names.
+pub fn lookup(name: &str) -> Option<&'static Currency> {
+    let name = name.to_lowercase();
+    CURRENCIES.iter().find(|currency| {
+        currency.code.eq_ignore_ascii_case(&name) || currency.names.iter().any(|n| *n == name)
+    })
+}
+
+/// Exchange rates, as of some date, read from a file the user keeps up to date.
+///
+/// It's the same shape most rate APIs answer with, so a saved response works
+/// as is, e.g.
+///
+/// ```toml
+/// date = "2023-12-01"
+/// base = "USD"
+///
+/// [rates]
+/// EUR = 0.9185
+/// JPY = 147.62
+/// ```
+#[derive(Debug, Default, Deserialize)]
+pub struct Rates {
+    pub date: String,
+    pub base: String,
+    /// How much of each currency one of the base currency buys.
+    pub rates: HashMap<String, f64>,
+}
+
+impl Rates {
+    /// Reads a TOML file, or a JSON one if that's what its extension says.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read exchange rates from {}", path.display()))?;
+
+        let rates = if path.extension().is_some_and(|ext| ext == "json") {
+            serde_json::from_str(&contents)?
+        } else {
+            toml::from_str(&contents)?
+        };
+
+        Ok(rates)
+    }
+
+    /// How much of the base currency one of `code` is worth.
+    fn rate(&self, code: &str) -> Option<f64> {
+        if code == self.base {
+            return Some(1.0);
+        }
+
+        self.rates.get(code).copied()
+    }
+
+    /// Converts an amount of money into another currency.
+    pub(crate) fn exchange(&self, money: Money, to: &'static Currency) -> Result<Money> {
+        if money.currency == to {
+            return Ok(money);
+        }
+
+        let missing = || OperandError::MissingRate {
+            from: money.currency.code,
+            to: to.code,
+        };
+
+        let from_rate = self.rate(money.currency.code).ok_or_else(missing)?;
+        let to_rate = self.rate(to.code).ok_or_else(missing)?;
+
+        Ok(Money {
+            amount: money.amount / from_rate * to_rate,
+            currency: to,
+            as_of: Some(self.date.clone()),
+        })
+    }
+}
+
+/// An amount of money in some currency.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Money {
+    amount: f64,
+    currency: &'static Currency,
+    /// The date of the rates used to get this amount, if it was converted.
+    as_of: Option<String>,
+}
+
+impl Money {
+    pub(crate) fn new(amount: f64, currency: &'static Currency) -> Self {
+        Self {
+            amount,
+            currency,
+            as_of: None,
+        }
+    }
+
+    pub(crate) fn currency(&self) -> &'static Currency {
+        self.currency
+    }
+
+    fn expect_same_currency(&self, other: &Money) -> Result<()> {
+        if self.currency != other.currency {
+            return Err(OperandError::MissingRate {
+                from: other.currency.code,
+                to: self.currency.code,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Keeps the date of whichever operand's amount came from converting.
+    fn with_amount(self, other: Money, amount: f64) -> Money {
+        Money {
+            amount,
+            currency: self.currency,
+            as_of: self.as_of.or(other.as_of),
+        }
+    }
+
+    pub(crate) fn add(self, other: Money) -> Result<Money> {
+        self.expect_same_currency(&other)?;
+        let amount = self.amount + other.amount;
+        Ok(self.with_amount(other, amount))
+    }
+
+    pub(crate) fn sub(self, other: Money) -> Result<Money> {
+        self.expect_same_currency(&other)?;
+        let amount = self.amount - other.amount;
+        Ok(self.with_amount(other, amount))
+    }
+
+    /// How many times one amount goes into the other, which is just a number.
+    pub fn ratio(&self, other: &Money) -> Result<Value> {
+        self.expect_same_currency(other)?;
+        Ok((self.amount / other.amount).into())
+    }
+
+    pub fn scale(self, factor: f64) -> Money {
+        Money {
+            amount: self.amount * factor,
+            ..self
+        }
+    }
+
+    pub fn compare(&self, other: &Money) -> Result<Option<Ordering>> {
+        self.expect_same_currency(other)?;
+        Ok(self.amount.partial_cmp(&other.amount))
+    }
+}
+
+impl Display for Money {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let decimals = self.currency.decimals;
+        write!(f, "{:.*} {}", decimals, self.amount, self.currency.code)?;
+
+        if let Some(date) = &self.as_of {
+            write!(f, " (rates as of {date})")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl From<Money> for Value {
+    fn from(value: Money) -> Self {
+        Self::Money(value)
+    }
+}
@@ -0,0 +1,238 @@
+use super::{complex::Complex, matrix::Matrix, value::Value};
+
+/// The highest degree of polynomial an equation is recognized as.
+const MAX_DEGREE: usize = 8;
+
+/// Where a fitted polynomial is checked against the equation, away from the
+/// whole numbers it was fitted at.
+const CHECK_POINTS: [f64; 3] = [-1.75, 0.5, 3.25];
+
+/// How far off a root found numerically may be, before it's polished.
+const TOLERANCE: f64 = 1e-9;
+
+/// A polynomial in one unknown, by its coefficients from the constant term up.
+#[derive(Debug, PartialEq)]
+pub struct Polynomial {
+    coefficients: Vec<f64>,
+}
+
+impl Polynomial {
+    /// Works out the polynomial `f` is, if it is one, by fitting polynomials of
+    /// increasing degree through its values at 0, 1, 2, ... until one also agrees
+    /// with it elsewhere.
+    pub fn fit<E>(mut f: impl FnMut(f64) -> Result<f64, E>) -> Result<Option<Self>, E> {
+        let mut samples = vec![];
+        let checks = CHECK_POINTS
+            .iter()
+            .map(|&x| Ok((x, f(x)?)))
+            .collect::<Result<Vec<_>, E>>()?;
+
+        for degree in 0..=MAX_DEGREE {
+            samples.push(f(degree as f64)?);
+
+            if samples.iter().any(|y| !y.is_finite()) {
+                return Ok(None);
+            }
+
+            let polynomial = match Self::through(&samples) {
+                Some(polynomial) => polynomial,
+                None => continue,
+            };
+
+            let agrees = checks.iter().all(|&(x, y)| {
+                let difference = (polynomial.eval(Complex::new(x, 0.0)).re - y).abs();
+                difference <= TOLERANCE * y.abs().max(1.0)
+            });
+
+            if agrees {
+                return Ok(Some(polynomial));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// The polynomial of the lowest degree that takes on `samples` at 0, 1, 2, ...
+    fn through(samples: &[f64]) -> Option<Self> {
+        let points = (0..samples.len())
+            .map(|x| {
+                (0..samples.len())
+                    .map(|power| Value::Float((x as f64).powi(power as i32)))
+                    .collect::<Vec<_>>()
+                    .into()
+            })
+            .collect::<Vec<Value>>();
+        let values = samples
+            .iter()
+            .copied()
+            .map(Value::Float)
+            .collect::<Vec<_>>();
+
+        let vandermonde = Matrix::from_rows(&points)?;
+        let solution = match vandermonde
+            .solve(&Matrix::column(&values)?)
+            .ok()?
+            .into_list()
+        {
+            Value::List(items) => items,
+            _ => return None,
+        };
+
+        let mut coefficients = solution
+            .iter()
+            .map(|c| {
+                // fitting leaves whole coefficients a hair off
+                let c = c.as_f64();
+                if (c - c.round()).abs() < TOLERANCE {
+                    c.round()
+                } else {
+                    c
+                }
+            })
+            .collect::<Vec<_>>();
+
+        while coefficients.len() > 1 && coefficients.last() == Some(&0.0) {
+            coefficients.pop();
+        }
+
+        Some(Self { coefficients })
+    }
+
+    pub fn degree(&self) -> usize {
+        self.coefficients.len() - 1
+    }
+
+    pub fn eval(&self, x: Complex) -> Complex {
+        self.coefficients
+            .iter()
+            .rev()
+            .fold(Complex::new(0.0, 0.0), |sum, &c| {
+                sum.mul(x).add(Complex::new(c, 0.0))
+            })
+    }
+
+    fn derivative(&self) -> Polynomial {
+        Polynomial {
+            coefficients: self
+                .coefficients
+                .iter()
+                .enumerate()
+                .skip(1)
+                .map(|(power, c)| c * power as f64)
+                .collect(),
+        }
+    }
+
+    /// Every root, real or not, with repeated ones only given once. Up to quadratics
+    /// they're worked out exactly, beyond that numerically.
+    pub fn roots(&self) -> Vec<Complex> {
+        let mut roots = match self.coefficients[..] {
+            [] | [_] => vec![],
+            [c, b] => vec![Complex::new(-c / b, 0.0)],
+            [c, b, a] => quadratic_roots(a, b, c),
+            _ => self.numeric_roots(),
+        }
+        .into_iter()
+        .map(tidy)
+        .collect::<Vec<_>>();
+
+        roots.dedup_by(|a, b| a.sub(*b).modulus() < TOLERANCE);
+        roots
+    }
+
+    /// The Durand-Kerner method, finding all roots at once, after which the real
+    /// ones are polished with Newton's method.
+    fn numeric_roots(&self) -> Vec<Complex> {
+        let leading = self.coefficients[self.degree()];
+        let monic = Polynomial {
+            coefficients: self.coefficients.iter().map(|c| c / leading).collect(),
+        };
+
+        // the usual starting points, powers of a number that's neither real nor a root of unity
+        let seed = Complex::new(0.4, 0.9);
+        let mut roots = (0..self.degree())
+            .map(|k| seed.powi(k as i32))
+            .collect::<Vec<_>>();
+
+        for _ in 0..500 {
+            let mut change: f64 = 0.0;
+
+            for i in 0..roots.len() {
+                let denominator = (0..roots.len())
+                    .filter(|&j| j != i)
+                    .fold(Complex::new(1.0, 0.0), |product, j| {
+                        product.mul(roots[i].sub(roots[j]))
+                    });
+                let step = monic.eval(roots[i]).div(denominator);
+
+                roots[i] = roots[i].sub(step);
+                change = change.max(step.modulus());
+            }
+
+            if change < TOLERANCE * TOLERANCE {
+                break;
+            }
+        }
+
+        let derivative = self.derivative();
+
+        let mut roots = roots
+            .into_iter()
+            .map(|root| {
+                if root.im.abs() > TOLERANCE.sqrt() {
+                    return root;
+                }
+
+                let mut x = Complex::new(root.re, 0.0);
+                for _ in 0..8 {
+                    let slope = derivative.eval(x);
+                    if slope.modulus() == 0.0 {
+                        break;
+                    }
+                    x = x.sub(self.eval(x).div(slope));
+                }
+                Complex::new(x.re, 0.0)
+            })
+            .collect::<Vec<_>>();
+
+        roots.sort_by(|a, b| a.re.total_cmp(&b.re).then(a.im.total_cmp(&b.im)));
+        roots
+    }
+}
+
+/// The quadratic formula, in the form that doesn't lose precision when `b` is
+/// much bigger than the other coefficients.
+fn quadratic_roots(a: f64, b: f64, c: f64) -> Vec<Complex> {
+    let discriminant = b * b - 4.0 * a * c;
+
+    if discriminant < 0.0 {
+        let re = -b / (2.0 * a);
+        let im = (-discriminant).sqrt() / (2.0 * a);
+        return vec![Complex::new(re, -im.abs()), Complex::new(re, im.abs())];
+    }
+
+    let q = -0.5 * (b + b.signum() * discriminant.sqrt());
+
+    let mut roots = if q == 0.0 {
+        vec![0.0]
+    } else {
+        vec![q / a, c / q]
+    };
+
+    roots.sort_by(f64::total_cmp);
+    roots.into_iter().map(|r| Complex::new(r, 0.0)).collect()
+}
+
+/// Rounds off what's left of the numeric error, like the 2.0000000000000004 of a 2.
+fn tidy(root: Complex) -> Complex {
+    let round = |x: f64| {
+        let rounded = (x * 1e12).round() / 1e12;
+        if rounded == 0.0 {
+            0.0
+        } else {
+            rounded
+        }
+    };
+
+    Complex::new(round(root.re), round(root.im))
+}
@@ -0,0 +1,132 @@
+use std::{fmt::Display, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+use super::{session::Settings, value::Value};
+
+/// How the numbers in answers are written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    /// As few digits as it takes to read back the same number.
+    #[default]
+    Auto,
+    /// With the same number of decimal places every time.
+    Fixed,
+    /// Like 1.5e3.
+    Scientific,
+    /// Whole numbers in hexadecimal, with anything else written as usual.
+    Hex,
+    Octal,
+    Binary,
+}
+
+impl Format {
+    pub const NAMES: [&'static str; 6] = ["auto", "fixed", "sci", "hex", "oct", "bin"];
+}
+
+impl FromStr for Format {
+    type Err = UnknownFormat;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(Format::Auto),
+            "fixed" => Ok(Format::Fixed),
+            "sci" | "scientific" => Ok(Format::Scientific),
+            "hex" | "hexadecimal" => Ok(Format::Hex),
+            "oct" | "octal" => Ok(Format::Octal),
+            "bin" | "binary" => Ok(Format::Binary),
+            _ => Err(UnknownFormat(s.to_string())),
+        }
+    }
+}
+
+impl Display for Format {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Format::Auto => "auto",
+            Format::Fixed => "fixed",
+            Format::Scientific => "sci",
+            Format::Hex => "hex",
+            Format::Octal => "oct",
+            Format::Binary => "bin",
+        };
+
+        write!(f, "{name}")
+    }
+}
+
+#[derive(Debug)]
+pub struct UnknownFormat(String);
+
+impl Display for UnknownFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "unknown format {}, expected one of {}",
+            self.0,
+            Format::NAMES.join(", ")
+        )
+    }
+}
+
+impl std::error::Error for UnknownFormat {}
+
+/// Writes out an answer, with its numbers in the session's format and precision.
+/// Anything that isn't a plain number, or a list of them, reads as it always does.
+pub fn answer(value: &Value, settings: &Settings) -> String {
+    match value {
+        Value::Integer(i) => integer(*i, settings),
+        Value::Float(f) => float(*f, settings),
+        Value::List(items) => {
+            let items = items
+                .iter()
+                .map(|item| answer(item, settings))
+                .collect::<Vec<_>>();
+
+            format!("[{}]", items.join(", "))
+        }
+        other => other.to_string(),
+    }
+}
+
+fn integer(i: isize, settings: &Settings) -> String {
+    match settings.format {
+        Format::Auto => i.to_string(),
+        Format::Hex => format!("{i:#x}"),
+        Format::Octal => format!("{i:#o}"),
+        Format::Binary => format!("{i:#b}"),
+        Format::Fixed | Format::Scientific => float(i as f64, settings),
+    }
+}
+
+fn float(f: f64, settings: &Settings) -> String {
+    // the digits after the point, or of the mantissa, like C's printf does
+    const DEFAULT_PRECISION: usize = 6;
+
+    match (settings.format, settings.precision) {
+        (Format::Fixed, precision) => {
+            format!("{:.*}", precision.unwrap_or(DEFAULT_PRECISION), f)
+        }
+        (Format::Scientific, Some(precision)) => format!("{f:.precision$e}"),
+        (Format::Scientific, None) => format!("{f:e}"),
+        (Format::Hex | Format::Octal | Format::Binary, _)
+            if f.fract() == 0.0 && f.abs() < isize::MAX as f64 =>
+        {
+            integer(f as isize, settings)
+        }
+        (_, Some(precision)) => {
+            let rounded = format!("{f:.precision$}");
+
+            if rounded.contains('.') {
+                rounded
+                    .trim_end_matches('0')
+                    .trim_end_matches('.')
+                    .to_string()
+            } else {
+                rounded
+            }
+        }
+        (_, None) => f.to_string(),
+    }
+}
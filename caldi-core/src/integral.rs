@@ -0,0 +1,96 @@
+/// How close the estimate of each piece of the integral has to get, which gets
+/// split between the halves each time a piece is.
+const TOLERANCE: f64 = 1e-10;
+
+/// How many times a piece may be halved, which stops singularities from being
+/// chased forever.
+const MAX_DEPTH: usize = 50;
+
+/// A stretch of the interval that's still to be estimated, along with what's
+/// already known about it.
+struct Piece {
+    a: f64,
+    b: f64,
+    fa: f64,
+    fm: f64,
+    fb: f64,
+    estimate: f64,
+    tolerance: f64,
+    depth: usize,
+}
+
+/// Integrates `f` from `a` to `b` with adaptive Simpson's rule, which halves the
+/// pieces of the interval where the estimate isn't good enough yet. Gives up, with
+/// `None`, if that takes more than `max_evaluations` calls to `f`.
+pub fn integrate<E>(
+    mut f: impl FnMut(f64) -> Result<f64, E>,
+    a: f64,
+    b: f64,
+    max_evaluations: usize,
+) -> Result<Option<f64>, E> {
+    if max_evaluations < 3 {
+        return Ok(None);
+    }
+
+    let (fa, fm, fb) = (f(a)?, f((a + b) / 2.0)?, f(b)?);
+    let mut evaluations = 3;
+
+    let mut pieces = vec![Piece {
+        a,
+        b,
+        fa,
+        fm,
+        fb,
+        estimate: simpson(a, b, fa, fm, fb),
+        tolerance: TOLERANCE,
+        depth: 0,
+    }];
+    let mut total = 0.0;
+
+    while let Some(piece) = pieces.pop() {
+        if evaluations + 2 > max_evaluations {
+            return Ok(None);
+        }
+
+        let m = (piece.a + piece.b) / 2.0;
+        let (flm, frm) = (f((piece.a + m) / 2.0)?, f((m + piece.b) / 2.0)?);
+        evaluations += 2;
+
+        let left = simpson(piece.a, m, piece.fa, flm, piece.fm);
+        let right = simpson(m, piece.b, piece.fm, frm, piece.fb);
+        let error = left + right - piece.estimate;
+
+        if piece.depth >= MAX_DEPTH || error.abs() <= 15.0 * piece.tolerance {
+            // Richardson extrapolation, which is what the 15 is about
+            total += left + right + error / 15.0;
+            continue;
+        }
+
+        pieces.push(Piece {
+            a: piece.a,
+            b: m,
+            fa: piece.fa,
+            fm: flm,
+            fb: piece.fm,
+            estimate: left,
+            tolerance: piece.tolerance / 2.0,
+            depth: piece.depth + 1,
+        });
+        pieces.push(Piece {
+            a: m,
+            b: piece.b,
+            fa: piece.fm,
+            fm: frm,
+            fb: piece.fb,
+            estimate: right,
+            tolerance: piece.tolerance / 2.0,
+            depth: piece.depth + 1,
+        });
+    }
+
+    Ok(Some(total))
+}
+
+fn simpson(a: f64, b: f64, fa: f64, fm: f64, fb: f64) -> f64 {
+    (b - a) / 6.0 * (fa + 4.0 * fm + fb)
+}
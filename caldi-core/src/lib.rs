@@ -0,0 +1,1335 @@
+//! The natural language calculator behind caldi, for other programs to embed
+//! without the voice assistant.
+//!
+//! [`eval`] answers a single problem, while a [`Session`] remembers variables,
+//! functions and history from one problem to the next:
+//!
+//! ```
+//! use caldi_core::{render_error, Session};
+//!
+//! assert_eq!(caldi_core::eval("what is 2 plus 3?").unwrap(), "5");
+//!
+//! let mut session = Session::default();
+//! session.eval("x = 4").unwrap();
+//! assert_eq!(session.eval("x squared").unwrap(), "16");
+//!
+//! let error = session.eval("2 km + 3 kg").unwrap_err();
+//! let rendered = render_error(error, "2 km + 3 kg");
+//! assert!(rendered.ends_with("incompatible units, length and mass, at position 5"));
+//! ```
+//!
+//! The [`Parser`] and the [`ast`] it parses to are here too, for looking at a
+//! problem without answering it, and the answers themselves as [`Value`]s come
+//! with [`report`].
+
+use std::cmp::Ordering;
+
+use chrono::{Datelike, NaiveDate, NaiveTime};
+
+use self::{
+    equation::Polynomial,
+    parse::ast::{
+        BinOp, BinaryExpr, Call, DateLiteral, Derivative, Dice, Equation, Expr, IfExpr, Iterated,
+        Iteration, TimeLiteral, UnOp, UnaryExpr,
+    },
+    session::{Scope, MAX_CALL_DEPTH},
+};
+
+pub use self::{
+    calendar::Duration,
+    complex::Complex,
+    currency::{Money, Rates},
+    format::{Format, UnknownFormat},
+    matrix::Matrix,
+    parse::{ast, error::ErrorKind, Parser},
+    random::Coin,
+    report::{report, report_error},
+    session::{Exchange, Session, Settings},
+    symbolic::Term,
+    units::Quantity,
+    value::{OperandError, Value},
+};
+
+mod builtins;
+mod calendar;
+mod complex;
+mod currency;
+mod equation;
+mod format;
+mod integral;
+mod matrix;
+pub mod parse;
+mod random;
+mod report;
+pub mod script;
+mod session;
+mod symbolic;
+mod units;
+mod value;
+
+/// Answers `source` on its own, without any exchange rates, as it would be
+/// written out by caldi.
+pub fn eval(source: &str) -> parse::Result<String> {
+    Session::default().eval(source)
+}
+
+fn eval_expr(expr: &Expr, scope: &Scope) -> parse::Result<Value> {
+    match expr {
+        Expr::Integer(i) => Ok((*i).into()),
+        Expr::BinExpr(expr) => eval_binary_expr(expr, scope),
+        Expr::UnExpr(expr) => eval_unary_expr(expr, scope),
+        Expr::Float(f) => Ok((*f).into()),
+        Expr::If(expr) => eval_if_expr(expr, scope),
+        Expr::Var(var) => Ok(scope
+            .variable(&var.name)
+            .cloned()
+            // a bare unit, as in the `h` of `km/h`, or the target of a conversion
+            .or_else(|| unit_value(&var.name))
+            .or_else(|| calendar::named(&var.name))
+            .or_else(|| imaginary_unit(&var.name))
+            .or_else(|| constant(&var.name))
+            // anything else is an unknown, which the answer is worked out in terms of
+            .unwrap_or_else(|| Term::Symbol(var.name.clone()).into())),
+        Expr::Unit(unit) => scope
+            .local(&unit.name)
+            .cloned()
+            .or_else(|| unit_value(&unit.name))
+            .ok_or_else(|| ErrorKind::UnknownVariable {
+                name: unit.name.clone(),
+                at: unit.at,
+            }),
+        Expr::Date(date) => eval_date(date),
+        Expr::Time(time) => eval_time(time),
+        Expr::Call(call) => eval_call(call, scope),
+        Expr::Equation(equation) => eval_equation(equation, scope),
+        Expr::Derivative(derivative) => eval_derivative(derivative, scope),
+        Expr::Iterated(iterated) => eval_iterated(iterated, scope),
+        Expr::Dice(dice) => eval_dice(dice, scope),
+        Expr::CoinFlip => Ok(Value::Coin(Coin::flip(&mut scope.rng()))),
+        Expr::List(items) => {
+            let items = items
+                .iter()
+                .map(|item| eval_expr(item, scope))
+                .collect::<parse::Result<Vec<_>>>()?;
+
+            // lists of equally long lists of numbers, like [[1, 2], [3, 4]], are matrices
+            Ok(Matrix::from_rows(&items)
+                .map(Value::from)
+                .unwrap_or_else(|| items.into()))
+        }
+    }
+}
+
+/// One of a unit of measure or currency, for numbers to be multiplied with.
+fn unit_value(name: &str) -> Option<Value> {
+    units::lookup(name)
+        .map(|unit| Quantity::new(1.0, unit).into())
+        .or_else(|| currency::lookup(name).map(|currency| Money::new(1.0, currency).into()))
+}
+
+fn imaginary_unit(name: &str) -> Option<Value> {
+    matches!(name, "i" | "j").then_some(Value::Complex(Complex::I))
+}
+
+/// Names of the constants, for completing them.
+const CONSTANTS: [&str; 3] = ["pi", "tau", "i"];
+
+fn constant(name: &str) -> Option<Value> {
+    match name {
+        "pi" => Some(std::f64::consts::PI.into()),
+        "tau" => Some(std::f64::consts::TAU.into()),
+        _ => None,
+    }
+}
+
+fn eval_date(date: &DateLiteral) -> parse::Result<Value> {
+    let year = date.year.unwrap_or_else(|| calendar::today().year());
+
+    NaiveDate::from_ymd_opt(year, date.month, date.day)
+        .map(Value::from)
+        .ok_or(ErrorKind::InvalidDate { at: date.at })
+}
+
+fn eval_time(time: &TimeLiteral) -> parse::Result<Value> {
+    NaiveTime::from_hms_opt(time.hour, time.minute, 0)
+        .map(Value::from)
+        .ok_or(ErrorKind::InvalidTime { at: time.at })
+}
+
+/// The values of the unknown that solve the equation: just the one if that's all
+/// there is, or else a list of them. Complex ones are left out of it unless the
+/// session is in complex mode.
+fn eval_equation(equation: &Equation, scope: &Scope) -> parse::Result<Value> {
+    let unknown = match &equation.unknown {
+        Some(unknown) => unknown.name.clone(),
+        None => find_unknown(&[&equation.left, &equation.right], equation.at, scope)?,
+    };
+
+    // the equation is solved as left - right = 0
+    let difference = |x: f64| {
+        let scope = scope.with_variable(&unknown, x.into());
+        let left = eval_expr(&equation.left, &scope)?;
+        let right = eval_expr(&equation.right, &scope)?;
+
+        match apply_binary_op(BinOp::Minus, left, right).map_err(|e| e.at(equation.at))? {
+            difference if difference.is_number() => Ok(difference.as_f64()),
+            other => Err(OperandError::expected("number", &other).at(equation.at)),
+        }
+    };
+
+    let polynomial = Polynomial::fit(difference)?.ok_or_else(|| ErrorKind::NotPolynomial {
+        unknown: unknown.clone(),
+        at: equation.at,
+    })?;
+
+    if polynomial.degree() == 0 {
+        return Err(if polynomial.eval(Complex::new(0.0, 0.0)).re == 0.0 {
+            ErrorKind::EverySolution {
+                unknown,
+                at: equation.at,
+            }
+        } else {
+            ErrorKind::NoSolution {
+                unknown,
+                at: equation.at,
+            }
+        });
+    }
+
+    let mut roots = polynomial
+        .roots()
+        .into_iter()
+        .filter(|root| root.im == 0.0 || scope.settings().complex)
+        .map(Value::from)
+        .collect::<Vec<_>>();
+
+    match roots.len() {
+        0 => Err(ErrorKind::NoSolution {
+            unknown,
+            at: equation.at,
+        }),
+        1 => Ok(roots.remove(0)),
+        _ => Ok(roots.into()),
+    }
+}
+
+fn eval_derivative(derivative: &Derivative, scope: &Scope) -> parse::Result<Value> {
+    let variable = match &derivative.variable {
+        Some(variable) => variable.name.clone(),
+        None => find_unknown(&[&derivative.expr], derivative.at, scope)?,
+    };
+
+    // the variable stays unknown, even if it's been given a value before
+    let scope = scope.with_variable(&variable, Term::Symbol(variable.clone()).into());
+
+    eval_expr(&derivative.expr, &scope)?
+        .into_term()
+        .map_err(|e| e.at(derivative.at))?
+        .derivative(&variable)
+        .map(Value::from)
+        .ok_or(ErrorKind::NotDifferentiable { at: derivative.at })
+}
+
+/// A sum or product of the body over a range of whole numbers, or its integral,
+/// taking no more than the session's limit of steps.
+fn eval_iterated(iterated: &Iterated, scope: &Scope) -> parse::Result<Value> {
+    let variable = match &iterated.variable {
+        Some(variable) => variable.name.clone(),
+        None => find_unknown(&[&iterated.body], iterated.at, scope)?,
+    };
+
+    let limit = scope.settings().max_iterations;
+    let from = eval_expr(&iterated.from, scope)?;
+    let to = eval_expr(&iterated.to, scope)?;
+
+    let (op, mut total) = match iterated.kind {
+        Iteration::Sum => (BinOp::Plus, Value::Integer(0)),
+        Iteration::Product => (BinOp::Times, Value::Integer(1)),
+        Iteration::Integral => return eval_integral(iterated, &variable, from, to, scope),
+    };
+
+    let from = from.into_integer().map_err(|e| e.at(iterated.at))?;
+    let to = to.into_integer().map_err(|e| e.at(iterated.at))?;
+
+    if (to as i128 - from as i128) >= limit as i128 {
+        return Err(ErrorKind::IterationLimit {
+            limit,
+            at: iterated.at,
+        });
+    }
+
+    for k in from..=to {
+        let scope = scope.with_variable(&variable, k.into());
+        let term = eval_expr(&iterated.body, &scope)?;
+        total = apply_binary_op(op, total, term).map_err(|e| e.at(iterated.at))?;
+    }
+
+    Ok(total)
+}
+
+fn eval_integral(
+    iterated: &Iterated,
+    variable: &str,
+    from: Value,
+    to: Value,
+    scope: &Scope,
+) -> parse::Result<Value> {
+    let bound = |value: Value| match value {
+        value if value.is_number() => Ok(value.as_f64()),
+        other => Err(OperandError::expected("number", &other).at(iterated.at)),
+    };
+    let (from, to) = (bound(from)?, bound(to)?);
+
+    let f = |x: f64| {
+        let scope = scope.with_variable(variable, x.into());
+
+        match eval_expr(&iterated.body, &scope)? {
+            y if y.is_number() => Ok(y.as_f64()),
+            other => Err(OperandError::expected("number", &other).at(iterated.at)),
+        }
+    };
+
+    let limit = scope.settings().max_iterations;
+
+    integral::integrate(f, from, to, limit)?
+        .map(Value::from)
+        .ok_or(ErrorKind::IterationLimit {
+            limit,
+            at: iterated.at,
+        })
+}
+
+/// The total of a roll of dice, of which there can't be more than the session's
+/// limit of steps.
+fn eval_dice(dice: &Dice, scope: &Scope) -> parse::Result<Value> {
+    let limit = scope.settings().max_iterations;
+    if dice.count > limit {
+        return Err(ErrorKind::IterationLimit { limit, at: dice.at });
+    }
+
+    random::roll(&mut scope.rng(), dice.count, dice.sides)
+        .map(Value::from)
+        .map_err(|e| e.at(dice.at))
+}
+
+/// The one name in these expressions that isn't already something else.
+fn find_unknown(exprs: &[&Expr], at: usize, scope: &Scope) -> parse::Result<String> {
+    let mut candidates: Vec<String> = vec![];
+
+    for var in exprs.iter().flat_map(|expr| expr.variables()) {
+        let known = scope.variable(&var.name).is_some()
+            || unit_value(&var.name).is_some()
+            || calendar::named(&var.name).is_some()
+            || imaginary_unit(&var.name).is_some()
+            || constant(&var.name).is_some();
+
+        if !known && !candidates.contains(&var.name) {
+            candidates.push(var.name.clone());
+        }
+    }
+
+    // failing anything else, a unit written against a number, like the t of 3t
+    if candidates.is_empty() {
+        for unit in exprs.iter().flat_map(|expr| expr.units()) {
+            if scope.variable(&unit.name).is_none() && !candidates.contains(&unit.name) {
+                candidates.push(unit.name.clone());
+            }
+        }
+    }
+
+    match <[String; 1]>::try_from(candidates) {
+        Ok([unknown]) => Ok(unknown),
+        Err(candidates) => Err(ErrorKind::UnclearUnknown { candidates, at }),
+    }
+}
+
+fn eval_call(call: &Call, scope: &Scope) -> parse::Result<Value> {
+    let args = || {
+        call.args
+            .iter()
+            .map(|arg| eval_expr(arg, scope))
+            .collect::<parse::Result<Vec<_>>>()
+    };
+
+    let function = match scope.function(&call.name) {
+        Some(function) => function,
+        None => {
+            return builtins::call(&call.name, args()?, scope)
+                .ok_or_else(|| ErrorKind::UnknownFunction {
+                    name: call.name.clone(),
+                    at: call.at,
+                })?
+                .map_err(|e| e.at(call.at))
+        }
+    };
+
+    if function.params.len() != call.args.len() {
+        return Err(ErrorKind::WrongArgumentCount {
+            name: call.name.clone(),
+            expected: function.params.len(),
+            found: call.args.len(),
+            at: call.at,
+        });
+    }
+
+    if scope.depth() >= MAX_CALL_DEPTH {
+        return Err(ErrorKind::RecursionLimit {
+            name: call.name.clone(),
+            at: call.at,
+        });
+    }
+
+    eval_expr(&function.body, &scope.call(function, args()?))
+}
+
+fn eval_if_expr(expr: &IfExpr, scope: &Scope) -> parse::Result<Value> {
+    let condition = eval_expr(&expr.condition, scope)?
+        .into_bool()
+        .map_err(|e| e.at(expr.at))?;
+
+    if condition {
+        eval_expr(&expr.then, scope)
+    } else {
+        eval_expr(&expr.otherwise, scope)
+    }
+}
+
+fn eval_binary_expr(expr: &BinaryExpr, scope: &Scope) -> parse::Result<Value> {
+    let left: Value = eval_expr(&expr.left, scope)?;
+
+    let right = eval_expr(&expr.right, scope)?;
+
+    // Amounts of money are brought into the same currency, which takes the session's
+    // exchange rates, before anything else is done with them.
+    let (left, right) = match (left, right) {
+        (Value::Money(l), Value::Money(r)) if matches!(expr.op, BinOp::In) => {
+            let money = scope.rates().exchange(l, r.currency());
+            return money.map(Value::from).map_err(|e| e.at(expr.at));
+        }
+        (Value::Money(l), Value::Money(r)) if l.currency() != r.currency() => {
+            let r = scope
+                .rates()
+                .exchange(r, l.currency())
+                .map_err(|e| e.at(expr.at))?;
+
+            (l.into(), r.into())
+        }
+        operands => operands,
+    };
+
+    apply_binary_op(expr.op, left, right).map_err(|e| match e {
+        OperandError::ShapeMismatch { left, right } => ErrorKind::ShapeMismatch {
+            left,
+            right,
+            operands: Some(expr.operands),
+            at: expr.at,
+        },
+        e => e.at(expr.at),
+    })
+}
+
+fn apply_binary_op(op: BinOp, left: Value, right: Value) -> value::Result<Value> {
+    let value = match op {
+        BinOp::Plus => (left + right)?,
+        BinOp::Minus => (left - right)?,
+        BinOp::Times => (left * right)?,
+        BinOp::ElementTimes => left.elementwise_mul(right)?,
+        BinOp::Over => (left / right)?,
+        BinOp::Power => left.pow(right)?,
+        BinOp::In => left.convert(right)?,
+        BinOp::BitAnd | BinOp::BitOr | BinOp::Xor => match (left, right) {
+            (Value::Bool(l), Value::Bool(r)) => match op {
+                BinOp::BitAnd => l & r,
+                BinOp::BitOr => l | r,
+                _ => l ^ r,
+            }
+            .into(),
+            (left, right) => {
+                let (l, r) = (left.into_integer()?, right.into_integer()?);
+                match op {
+                    BinOp::BitAnd => l & r,
+                    BinOp::BitOr => l | r,
+                    _ => l ^ r,
+                }
+                .into()
+            }
+        },
+        BinOp::ShiftLeft | BinOp::ShiftRight => {
+            let left = left.into_integer()?;
+            let right = right.into_integer()?;
+
+            let shifted = u32::try_from(right).ok().and_then(|amount| match op {
+                // shifting set bits off the top, or into the sign bit, overflows
+                BinOp::ShiftLeft => left
+                    .checked_shl(amount)
+                    .filter(|shifted| shifted >> amount == left),
+                _ => left.checked_shr(amount),
+            });
+
+            shifted.ok_or(OperandError::Overflow)?.into()
+        }
+        BinOp::Equal => left.equals(&right)?.into(),
+        BinOp::NotEqual => (!left.equals(&right)?).into(),
+        BinOp::Less => (left.compare(&right)? == Some(Ordering::Less)).into(),
+        BinOp::Greater => (left.compare(&right)? == Some(Ordering::Greater)).into(),
+        BinOp::LessEqual => matches!(
+            left.compare(&right)?,
+            Some(Ordering::Less | Ordering::Equal)
+        )
+        .into(),
+        BinOp::GreaterEqual => matches!(
+            left.compare(&right)?,
+            Some(Ordering::Greater | Ordering::Equal)
+        )
+        .into(),
+    };
+
+    Ok(value)
+}
+
+fn eval_unary_expr(expr: &UnaryExpr, scope: &Scope) -> parse::Result<Value> {
+    let number = eval_expr(&expr.right, scope)?;
+
+    let value = match expr.op {
+        UnOp::Plus => Ok(number),
+        UnOp::Minus => -number,
+        UnOp::Not => match number {
+            Value::Bool(b) => Ok((!b).into()),
+            number => number.into_integer().map(|i| (!i).into()),
+        },
+    };
+
+    value.map_err(|e| e.at(expr.at))
+}
+
+/// Writes out `source` with what went wrong with it pointed out beneath, as in:
+///
+/// ```text
+/// 2 km + 3 kg
+///      ↳ incompatible units, length and mass, at position 5
+/// ```
+pub fn render_error(error: ErrorKind, source: &str) -> String {
+    let mut output = String::new();
+
+    let location = error.position();
+
+    output.push_str(source);
+
+    output.push_str(&" ".repeat(location));
+    output.push('\n');
+
+    // underlining the operands that don't go together
+    if let ErrorKind::ShapeMismatch {
+        operands: Some(operands),
+        ..
+    } = &error
+    {
+        let mut underline = String::new();
+        for span in operands {
+            underline.push_str(&" ".repeat(span.start.saturating_sub(underline.len())));
+            underline.push_str(&"~".repeat(span.end.saturating_sub(span.start)));
+        }
+        output.push_str(&underline);
+        output.push('\n');
+    }
+
+    output.push_str(&" ".repeat(location));
+
+    output.push('↳');
+    output.push(' ');
+
+    output.push_str(&error.to_string());
+
+    output
+}
+
+/// Like [`render_error`], but headed by the file, line and column the error is at,
+/// for a statement of a script.
+pub fn render_script_error(error: ErrorKind, statement: &script::Statement) -> String {
+    format!(
+        "{}:{}:{}\n{}",
+        statement.file.display(),
+        statement.line,
+        statement.column_of(error.position()),
+        render_error(error, statement.source)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use insta::assert_display_snapshot;
+
+    use crate::{
+        parse, render_error, render_script_error, report,
+        script::{self, Script},
+        Format, Session,
+    };
+
+    const RATES: &str = r#"
+        date = "2023-12-01"
+        base = "USD"
+
+        [rates]
+        EUR = 0.92
+        GBP = 0.79
+        JPY = 147.5
+    "#;
+
+    fn session() -> Session {
+        let mut session = Session::with_rates(toml::from_str(RATES).unwrap());
+        // so random numbers, dice and coin flips come out the same every run
+        session.seed(0);
+        session
+    }
+
+    fn eval(source: &str) -> parse::Result<String> {
+        session().eval(source)
+    }
+
+    /// Evaluates each line in the same session, answering with the last one.
+    fn eval_lines(lines: &[&str]) -> parse::Result<String> {
+        let mut session = session();
+        let (last, rest) = lines.split_last().expect("at least one line");
+
+        for line in rest {
+            session.eval(line)?;
+        }
+
+        session.eval(last)
+    }
+
+    macro_rules! assert_evals {
+        ($expr:literal, $ans:expr) => {
+            assert_eq!(eval($expr).unwrap(), ($ans).to_string())
+        };
+    }
+
+    #[test]
+    fn ops_in_letters() {
+        assert_evals!("minus 3", -3);
+        assert_evals!("negative 3", -3);
+        assert_evals!("2 plus 2 times 3 minus 1 over 2 minus negative 5", 12.5);
+    }
+
+    #[test]
+    fn unaries() {
+        assert_evals!("-2", -2);
+        assert_evals!("--2", 2);
+        assert_evals!("+2", 2);
+        assert_evals!("-+-2", 2);
+        assert_evals!("--+-2", -2);
+    }
+
+    #[test]
+    fn sums() {
+        assert_evals!("3 + 2", 5);
+        assert_evals!("3 + 3.", 6);
+        assert_evals!("3 - 2 / 6", 2.6666666666666665);
+        assert_evals!("75 + 100", 175);
+        assert_evals!("75 - 100", -25);
+        assert_evals!("75 + 1000 - 100", 975);
+        assert_evals!("1000 + 75 - 100", 975);
+    }
+
+    #[test]
+    fn products() {
+        assert_evals!("3 * 2", 6);
+        assert_evals!("89 * 34", 3026);
+        assert_evals!("89 * 34 * 23 * 199", 13850002);
+        assert_evals!("9 / 2", 4.5);
+        assert_evals!("256 * 9 / 2", 1152);
+        assert_evals!("2 + 9 x 2", 20);
+    }
+
+    #[test]
+    fn bitwise() {
+        assert_evals!("12 & 10", 8);
+        assert_evals!("12 | 3", 15);
+        assert_evals!("12 xor 10", 6);
+        assert_evals!("~0", -1);
+        assert_evals!("1 << 4", 16);
+        assert_evals!("256 >> 2 + 1", 32);
+        assert_evals!("1 | 2 & 3", 3);
+        assert_evals!("6 bitwise and 3", 2);
+        assert_evals!("6 bitwise or 3", 7);
+        assert_evals!("1 shifted left by 3", 8);
+        assert_evals!("what is 16 shifted right by 2", 4);
+        assert_evals!("bitwise not 7", -8);
+    }
+
+    #[test]
+    fn comparisons() {
+        assert_evals!("3 < 4", "yes");
+        assert_evals!("3 > 4", "no");
+        assert_evals!("4 <= 4.0", "yes");
+        assert_evals!("4 >= 5", "no");
+        assert_evals!("2 + 2 == 4", "yes");
+        assert_evals!("2 != 2", "no");
+        assert_evals!("1 < 2 == 3 < 4", "yes");
+        assert_evals!("is 5 greater than 3", "yes");
+        assert_evals!("is 3 times 7 greater than 20", "yes");
+        assert_evals!("is 2 less than or equal to 1", "no");
+        assert_evals!("is 10 over 4 equal to 2.5", "yes");
+        assert_evals!("6 is not equal to 6", "no");
+        assert_evals!("3 > 4 xor 1 < 2", "yes");
+        assert_evals!("1 < 2 & 3 < 4", "yes");
+    }
+
+    #[test]
+    fn conditionals() {
+        assert_evals!("if 3 > 2 then 10 else 20", 10);
+        assert_evals!("if 3 < 2 then 10 else 20", 20);
+        assert_evals!("1 + if 2 == 2 then 1 else 0", 2);
+        assert_evals!("if 1 > 2 then 1 else if 2 > 1 then 2 else 3", 2);
+        assert_evals!("if 1 < 2 then 1 else 1 << 64", 1);
+        assert_evals!("if 150 is at least 100 then 0 otherwise 150 times 0.1", 0);
+    }
+
+    #[test]
+    fn powers_and_grouping() {
+        assert_evals!("2 ^ 10", 1024);
+        assert_evals!("2 ^ 3 ^ 2", 512);
+        assert_evals!("-2 ^ 2", -4);
+        assert_evals!("2 ^ -1", 0.5);
+        assert_evals!("(2 + 3) * 4", 20);
+        assert_evals!("2 * (3 + 4) ^ 2", 98);
+        assert_evals!("3 raised to the power of 3", 27);
+    }
+
+    #[test]
+    fn user_functions() {
+        let defined = eval_lines(&["f(x) = x^2 + 1", "f(3)"]);
+        assert_eq!(defined.unwrap(), "10");
+
+        let defined = eval_lines(&["area(w, h) = w * h", "area(3, 4) + area(1, 2)"]);
+        assert_eq!(defined.unwrap(), "14");
+
+        let defined = eval_lines(&["define tip of x as x times 0.18", "what is tip of 50"]);
+        assert_eq!(defined.unwrap(), "9");
+
+        let defined = eval_lines(&["define area of w and h as w times h", "area of 3 and 5"]);
+        assert_eq!(defined.unwrap(), "15");
+
+        let defined = eval_lines(&["rate = 3", "let budget be 100", "budget - rate"]);
+        assert_eq!(defined.unwrap(), "97");
+
+        let defined = eval_lines(&[
+            "fact(n) = if n <= 1 then 1 else n * fact(n - 1)",
+            "fact(10)",
+        ]);
+        assert_eq!(defined.unwrap(), "3628800");
+    }
+
+    #[test]
+    fn function_parameters_are_lexically_scoped() {
+        let defined = eval_lines(&["x = 1", "f(x) = x * 10", "f(5) + x"]);
+        assert_eq!(defined.unwrap(), "51");
+
+        // h's y doesn't reach into g, where it's still unknown
+        let defined = eval_lines(&["g() = y", "h(y) = g()", "h(2)"]);
+        assert_eq!(defined.unwrap(), "y");
+    }
+
+    #[test]
+    fn parameters_named_like_units() {
+        let defined = eval_lines(&["f(t) = 3t + 1", "f(2)"]);
+        assert_eq!(defined.unwrap(), "7");
+        let defined = eval_lines(&["g(m) = 2m", "g(4)"]);
+        assert_eq!(defined.unwrap(), "8");
+        let defined = eval_lines(&["h(c) = c^2 + 1", "h(3)"]);
+        assert_eq!(defined.unwrap(), "10");
+
+        assert_evals!("d/dt 3t^2", "6t");
+        assert_evals!("sum(m, 1, 3, 2m)", 12);
+        assert_evals!("integrate(3c^2, c, 0, 2)", 8);
+
+        // only parameters and the like stand in for units, not the session's variables
+        assert_eq!(eval_lines(&["m = 3", "5 m"]).unwrap(), "5 m");
+    }
+
+    #[test]
+    fn lists_and_aggregates() {
+        assert_evals!("[1, 2, 3]", "[1, 2, 3]");
+        assert_evals!("[]", "[]");
+        assert_evals!("[1 + 1, 2 * 3]", "[2, 6]");
+        assert_evals!("sum([1, 2, 3])", 6);
+        assert_evals!("sum(1, 2, 3.5)", 6.5);
+        assert_evals!("product([2, 3, 4])", 24);
+        assert_evals!("mean([1, 2, 3, 4])", 2.5);
+        assert_evals!("median([5, 1, 3])", 3);
+        assert_evals!("median([4, 1, 3, 2])", 2.5);
+        assert_evals!("mode([1, 2, 2, 3, 3])", 2);
+        assert_evals!("min([4, -1, 3])", -1);
+        assert_evals!("max(4, 9.5, 3)", 9.5);
+        assert_evals!("variance([2, 4, 4, 4, 5, 5, 7, 9])", 4);
+        assert_evals!("stddev([2, 4, 4, 4, 5, 5, 7, 9])", 2);
+        assert_evals!("what is the average of 12, 18 and 30", 20);
+        assert_evals!("the sum of the numbers 4, 8 and 15", 27);
+        assert_evals!("the standard deviation of 2, 4, 4, 4, 5, 5, 7 and 9", 2);
+        assert_evals!("the numbers 4, 8 and 15", "[4, 8, 15]");
+    }
+
+    #[test]
+    fn units() {
+        assert_evals!("5 km", "5 km");
+        assert_evals!("5 km + 300 m", "5.3 km");
+        assert_evals!("5 miles in kilometers", "8.04672 km");
+        assert_evals!("72 F to C", "22.2222222222 °C");
+        assert_evals!("100 C in F", "212 °F");
+        assert_evals!("10 C + 5 C", "15 °C");
+        assert_evals!("3 feet 2 inches in cm", "96.52 cm");
+        assert_evals!("2 km * 500 m", "1 km^2");
+        assert_evals!("20 m^2 in ft^2", "215.278208334 ft^2");
+        assert_evals!("100 km / 2 h", "50 km/h");
+        assert_evals!("60 mph in km/h", "96.56064 km/h");
+        assert_evals!("5 kg * 9.8 m/s^2", "49 N");
+        assert_evals!("3 * 2 kg", "6 kg");
+        assert_evals!("10 km / 5 km", 2);
+        assert_evals!("1 h > 59 min", "yes");
+        assert_evals!("what is 3 cups in ml", "709.7647095 ml");
+    }
+
+    #[test]
+    fn currencies() {
+        assert_evals!("$20", "20.00 USD");
+        assert_evals!("50 euros", "50.00 EUR");
+        assert_evals!("$20 * 3 + $1.50", "61.50 USD");
+        assert_evals!("$20 in euros", "18.40 EUR (rates as of 2023-12-01)");
+        assert_evals!(
+            "what is 50 euros in yen",
+            "8016 JPY (rates as of 2023-12-01)"
+        );
+        assert_evals!("100 GBP to EUR", "116.46 EUR (rates as of 2023-12-01)");
+        assert_evals!("$10 + 10 euros", "20.87 USD (rates as of 2023-12-01)");
+        assert_evals!("$30 / $4", 7.5);
+        assert_evals!("$5 < 5 euros", "yes");
+        assert_evals!("$5 in dollars", "5.00 USD");
+        assert_evals!("€20", "20.00 EUR");
+        assert_evals!("£3.50 * 2", "7.00 GBP");
+        assert_evals!("¥500", "500 JPY");
+        assert_evals!("€20 in dollars", "21.74 USD (rates as of 2023-12-01)");
+    }
+
+    #[test]
+    fn dates_and_times() {
+        assert_evals!("March 3rd 2024", "Sunday, March 3, 2024");
+        assert_evals!(
+            "what's 90 days from March 3rd 2024",
+            "Saturday, June 1, 2024"
+        );
+        assert_evals!(
+            "the 3rd of March 2024 - 2 weeks",
+            "Sunday, February 18, 2024"
+        );
+        assert_evals!("29 Feb 2024", "Thursday, February 29, 2024");
+        assert_evals!(
+            "how many days between June 1 2024 and December 25 2024",
+            "207 days"
+        );
+        assert_evals!("how many days between yesterday and today", "1 day");
+        assert_evals!("today - yesterday", "1 day");
+        assert_evals!("3 hours 20 minutes times 4", "13 hours 20 minutes");
+        assert_evals!("2:45 pm plus 95 minutes", "4:20 PM");
+        assert_evals!("11 p.m. + 2 hours", "1:00 AM");
+        assert_evals!("5 pm - 9:30 am", "7 hours 30 minutes");
+        assert_evals!("1 hour 30 minutes in minutes", "90 mins");
+        assert_evals!("90 seconds / 1 min", 1.5);
+        assert_evals!("March 3rd 2024 < June 1 2024", "yes");
+        assert_evals!("noon > 9:15", "yes");
+        // numbers said as words, as speech is often transcribed
+        assert_evals!("March three 2024", "Sunday, March 3, 2024");
+        assert_evals!("the twenty nine of Feb 2024", "Thursday, February 29, 2024");
+        assert_evals!("three pm", "3:00 PM");
+        assert_evals!("eleven pm + 2 hours", "1:00 AM");
+    }
+
+    #[test]
+    fn complex_numbers() {
+        assert_evals!("3 + 4i", "3 + 4i");
+        assert_evals!("2j - 5", "-5 + 2i");
+        assert_evals!("i * i", -1);
+        assert_evals!("(1 + 2i) * (3 - i)", "5 + 5i");
+        assert_evals!("(1 + 2i) / (3 - 4i)", "-0.2 + 0.4i");
+        assert_evals!("-(2 - 3i)", "-2 + 3i");
+        assert_evals!("(1 + i)^2", "2i");
+        assert_evals!("abs(3 + 4i)", 5);
+        assert_evals!("conj(3 + 4i)", "3 - 4i");
+        assert_evals!("re(3 + 4i) + im(3 + 4i)", 7);
+        assert_evals!("arg(-i)", -std::f64::consts::FRAC_PI_2);
+        assert_evals!("sqrt(16)", 4);
+        assert_eq!(eval("sqrt(-4)").unwrap(), "NaN");
+    }
+
+    #[test]
+    fn complex_mode() {
+        let mut session = session();
+        session.settings.complex = true;
+
+        assert_eq!(session.eval("sqrt(-4)").unwrap(), "2i");
+        assert_eq!(session.eval("square root of -2.25").unwrap(), "1.5i");
+        assert_eq!(session.eval("sqrt(-2i)").unwrap(), "1 - i");
+        assert_eq!(session.eval("sqrt(9)").unwrap(), "3");
+    }
+
+    #[test]
+    fn matrices() {
+        let mut session = session();
+        let mut eval = |source| session.eval(source).unwrap();
+
+        assert_eq!(
+            eval("[[1, 2], [3, 4]] + [[1, 1], [1, 1]]"),
+            "[[2, 3], [4, 5]]"
+        );
+        assert_eq!(
+            eval("[[1, 2], [3, 4]] * [[5, 6], [7, 8]]"),
+            "[[19, 22], [43, 50]]"
+        );
+        assert_eq!(
+            eval("[[1, 2], [3, 4]] .* [[5, 6], [7, 8]]"),
+            "[[5, 12], [21, 32]]"
+        );
+        assert_eq!(
+            eval("[[1, 2], [3, 4]] elementwise times [[2, 2], [2, 2]]"),
+            "[[2, 4], [6, 8]]"
+        );
+        assert_eq!(eval("2 * [[1, 2], [3, 4]]"), "[[2, 4], [6, 8]]");
+        assert_eq!(
+            eval("transpose([[1, 2, 3], [4, 5, 6]])"),
+            "[[1, 4], [2, 5], [3, 6]]"
+        );
+        assert_eq!(eval("det([[1, 2], [3, 4]])"), "-2");
+        assert_eq!(eval("inverse([[1, 2], [3, 4]])"), "[[-2, 1], [1.5, -0.5]]");
+        assert_eq!(eval("solve([[2, 1], [1, 3]], [3, 5])"), "[0.8, 1.4]");
+        assert_eq!(eval("[[1, 1], [0, 1]] ^ 3"), "[[1, 3], [0, 1]]");
+        assert_eq!(eval("[[2, 0], [0, 4]] ^ -1"), "[[0.5, 0], [0, 0.25]]");
+        assert_eq!(eval("[[1, 2], [3, 4]] * [1, 1]"), "[3, 7]");
+    }
+
+    #[test]
+    fn equations() {
+        assert_evals!("solve 3x + 5 = 20 for x", 5);
+        assert_evals!("solve 2 * y - 1 = y + 3", 4);
+        assert_evals!("what x makes 3x plus 5 equal 20", 5);
+        assert_evals!("solve x^2 - 5x + 6 = 0", "[2, 3]");
+        assert_evals!("solve x^2 = 2x - 1", 1);
+        assert_evals!("solve x^3 - 6x^2 + 11x = 6", "[1, 2, 3]");
+        assert_evals!("solve x^3 - 3x + 2 = 0", "[-2, 1]");
+        assert_evals!("solve x^3 = 8", 2);
+        assert_evals!("2x3", 6);
+        assert_eq!(eval_lines(&["a = 2", "solve a * x = 7"]).unwrap(), "3.5");
+        assert_eq!(
+            eval_lines(&["x = 10", "solve x + 1 = 3 for x"]).unwrap(),
+            "2"
+        );
+        // unknowns named like units, as the t of 3t, or c of 5c
+        assert_evals!("solve 3t + 1 = 7 for t", 2);
+        assert_evals!("solve 5c = 10 for c", 2);
+        assert_evals!("solve 3t + 1 = 7", 2);
+        assert_evals!("solve 2m - 4 = 0", 2);
+    }
+
+    #[test]
+    fn complex_roots() {
+        let mut session = session();
+        session.settings.complex = true;
+
+        assert_eq!(session.eval("solve x^2 + 1 = 0").unwrap(), "[-i, i]");
+        assert_eq!(
+            session.eval("solve x^2 - 2x + 5 = 0").unwrap(),
+            "[1 - 2i, 1 + 2i]"
+        );
+        assert_eq!(
+            session.eval("solve x^3 = 1").unwrap(),
+            "[-0.5 - 0.866025403784i, -0.5 + 0.866025403784i, 1]"
+        );
+    }
+
+    #[test]
+    fn symbolic() {
+        assert_evals!("rate * 2", "2 * rate");
+        assert_evals!("2x + 3x", "5x");
+        assert_evals!("x + y - x", "y");
+        assert_evals!("x * x * 3", "3x^2");
+        assert_evals!("2 * (x + 1) - 2", "2x");
+        assert_evals!("x^2 * x^3 / x", "x^4");
+        assert_evals!("6x / 3", "2x");
+        assert_evals!("1 / x + 2", "1/x + 2");
+        assert_evals!("(x + 1)^2 - (x + 1)^2", 0);
+        assert_evals!("-(y - 4)", "-y + 4");
+        assert_evals!("sqrt(x) * 2", "2sqrt(x)");
+        assert_eq!(eval_lines(&["a = 3", "a * b + a"]).unwrap(), "3b + 3");
+    }
+
+    #[test]
+    fn derivatives() {
+        assert_evals!("d/dx x^3 + 2x", "3x^2 + 2");
+        assert_evals!("d/dx 5", 0);
+        assert_evals!("d/dv 4.9v^2 + 3v", "9.8v + 3");
+        assert_evals!("d/dx x * y", "y");
+        assert_evals!("d/dx 1/x", "-1/x^2");
+        assert_evals!("d/dx (x^2 + 1)^3", "6x * (x^2 + 1)^2");
+        assert_evals!("d/dx sqrt(x)", "0.5/sqrt(x)");
+        assert_evals!("the derivative of x^2 - 4x", "2x - 4");
+        assert_evals!("derivative of rate * y^2 with respect to y", "2 * rate * y");
+        assert_eq!(eval_lines(&["x = 2", "d/dx x^2"]).unwrap(), "2x");
+    }
+
+    #[test]
+    fn pemdas() {
+        assert_evals!("9 * 2 / 3 + 6 - 4 + 2", 10);
+        assert_evals!("2 + 2 / 2 - - 2", 5);
+        assert_evals!("2 + 2 * 3 - 1 / 2 - - 5", 12.5);
+        assert_evals!("3 - 1 / 2 - 5", -2.5);
+        assert_evals!("-3 - 1 / 2 - 5", -8.5)
+    }
+
+    macro_rules! assert_error {
+        ($source:literal) => {
+            let source = $source;
+            let err = eval(source).unwrap_err();
+            let prettied = render_error(err, source);
+            insta::with_settings!({ description => source }, {
+                assert_display_snapshot!(prettied)
+            })
+        };
+    }
+
+    #[test]
+    fn errors() {
+        assert_error!("* 2");
+        assert_error!("/ 2");
+        assert_error!("2 + * 2");
+        assert_error!("3 × 4");
+    }
+
+    #[test]
+    fn type_errors() {
+        assert_error!("1.5 & 3");
+        assert_error!("~2.0");
+        assert_error!("1 << 64");
+        assert_error!("1 < 2 < 3");
+        assert_error!("1 == 2 | 3");
+        assert_error!("if 1 then 2 else 3");
+        assert_error!("2i < 3");
+    }
+
+    #[test]
+    fn overflow_errors() {
+        assert_error!("9223372036854775807 + 1");
+        assert_error!("9223372036854775807 * 2");
+        assert_error!("-9223372036854775807 - 10");
+        assert_error!("-(-9223372036854775807 - 1)");
+        assert_error!("1 << 63");
+        assert_error!("3 << 62");
+    }
+
+    #[test]
+    fn conditional_errors() {
+        assert_error!("if 1 < 2 then 3");
+        assert_error!("if 1 < 2 else 3");
+    }
+
+    macro_rules! assert_session_error {
+        ($($line:literal),+) => {
+            let lines = [$($line),+];
+            let err = eval_lines(&lines).unwrap_err();
+            let source = lines.last().unwrap();
+            let prettied = render_error(err, source);
+            insta::with_settings!({ description => lines.join("\n") }, {
+                assert_display_snapshot!(prettied)
+            })
+        };
+    }
+
+    #[test]
+    fn function_errors() {
+        assert_error!("rate * 2 > 10");
+        assert_error!("f(2)");
+        assert_session_error!("f(x) = x", "f(1, 2)");
+        assert_session_error!("loop(n) = loop(n + 1)", "loop(0)");
+        assert_session_error!(
+            "fact(n) = if n <= 1 then 1 else n * fact(n - 1)",
+            "fact(21)"
+        );
+    }
+
+    #[test]
+    fn list_errors() {
+        assert_error!("mean([])");
+        assert_error!("sum([1, 2 < 3])");
+        assert_error!("[1, 2] + 1");
+    }
+
+    #[test]
+    fn currency_errors() {
+        assert_error!("$20 in swiss francs");
+        assert_error!("$20 + 3 kg");
+        assert_error!("₩5000");
+    }
+
+    #[test]
+    fn calendar_errors() {
+        assert_error!("February 30 2023");
+        assert_error!("13 pm");
+        assert_error!("10:75");
+        assert_error!("today + 3");
+    }
+
+    #[test]
+    fn matrix_errors() {
+        assert_error!("[[1, 2]] * [[1, 2]]");
+        assert_error!("[[1, 2], [3, 4]] + [[1, 2, 3]]");
+        assert_error!("det([[1, 2, 3], [4, 5, 6]])");
+        assert_error!("inverse([[1, 2], [2, 4]])");
+    }
+
+    #[test]
+    fn equation_errors() {
+        assert_error!("solve x + y = 3");
+        assert_error!("solve 2 = 3");
+        assert_error!("solve x + 1 = x + 1");
+        assert_error!("solve x + 1 = x");
+        assert_error!("solve x^2 = -1");
+        assert_error!("solve 2^x = 8");
+    }
+
+    #[test]
+    fn symbolic_errors() {
+        assert_error!("d/dx x^x");
+        assert_error!("the derivative of x * y");
+        assert_error!("x < 3");
+    }
+
+    #[test]
+    fn sums_products_and_integrals() {
+        assert_evals!("sum(k, 1, 100, k^2)", 338350);
+        assert_evals!("prod(k, 1, 5, k)", 120);
+        assert_evals!("the sum of k squared for k from 1 to 100", 338350);
+        assert_evals!("the product of 2k for k from 1 to 3", 48);
+        assert_evals!("sum(1, 2, 3, 4)", 10);
+        assert_evals!("integrate(x^2, 0, 1)", 0.3333333333333333);
+        assert_evals!("integrate(3 * u^2, u, 0, 2)", 8);
+        assert_evals!("the integral of x squared from 0 to 3", 9);
+        assert_evals!("5 meters squared", "5 m^2");
+
+        let defined = eval_lines(&["f(x) = 2x + 1", "sum(n, 0, 9, f(n))"]);
+        assert_eq!(defined.unwrap(), "100");
+    }
+
+    #[test]
+    fn iteration_errors() {
+        assert_error!("sum(k, 1, 1000000000, k)");
+        assert_error!("sum(k, 1, 2.5, k)");
+        assert_error!("integrate(1 / x, 0, 1)");
+        assert_error!("prod(k, 1, 30, k)");
+    }
+
+    #[test]
+    fn dice_and_coins() {
+        // what comes up depends on the version of fastrand, so only the ranges are
+        // checked, and that the same seed gives the same sequence
+        let in_range = |source: &str, low: f64, high: f64| {
+            let answer: f64 = eval(source).unwrap().parse().unwrap();
+            assert!((low..=high).contains(&answer), "{source} gave {answer}");
+        };
+        in_range("3d6", 3.0, 18.0);
+        in_range("roll two d20", 2.0, 40.0);
+        in_range("roll a d20 + 5", 6.0, 25.0);
+        in_range("rand()", 0.0, 1.0);
+        in_range("randint(1, 10)", 1.0, 10.0);
+        assert!(["heads", "tails"].contains(&eval("flip a coin").unwrap().as_str()));
+
+        let rolls = eval_lines(&[
+            "rolls = [4d6, 4d6, 4d6, 4d6, 4d6, 4d6]",
+            "min(rolls) >= 4 & max(rolls) <= 24",
+        ]);
+        assert_eq!(rolls.unwrap(), "yes");
+
+        let mut first = session();
+        let mut second = session();
+        for source in ["d100", "3d6", "flip a coin", "rand()", "randint(1, 10)"] {
+            assert_eq!(first.eval(source).unwrap(), second.eval(source).unwrap());
+        }
+    }
+
+    #[test]
+    fn random_errors() {
+        assert_error!("randint(10, 1)");
+        assert_error!("roll 2d0");
+        assert_error!("1000000d6");
+        assert_error!("randint(1.5, 3)");
+    }
+
+    #[test]
+    fn juxtaposition() {
+        assert_evals!("2pi", std::f64::consts::TAU);
+        assert_evals!("3(4+5)", 27);
+        assert_evals!("3 (4 + 5)", 27);
+        assert_evals!("(1 + 2)(3 + 4)", 21);
+        assert_evals!("2 y", "2y");
+        assert_evals!("x y z", "x * y * z");
+        assert_evals!("2 x + 1", "2x + 1");
+        assert_evals!("2 x 3", 6);
+        // written together, the 2y is taken as one, where spaced out it's like 6 / 2 * y
+        assert_evals!("6 / 2y", "3/y");
+        assert_evals!("6 / 2 y", "3y");
+        assert_evals!("two times three plus four", 10);
+        assert_evals!("twenty one minus one", 20);
+        assert_evals!("5 plus the sum of 1 and 2", 8);
+        // words said after the problem aren't multiplied into it
+        assert_evals!("5 times 6 thanks", 30);
+        assert_evals!("what is 2 plus 3 please", 5);
+        assert_evals!("2 plus 3, please", 5);
+        assert_evals!("2 plus 3, thank you!", 5);
+        assert_evals!("2 pi", std::f64::consts::TAU);
+
+        let defined = eval_lines(&["x = 5", "2 x"]);
+        assert_eq!(defined.unwrap(), "10");
+    }
+
+    #[test]
+    fn juxtaposition_errors() {
+        assert_error!("2 3");
+        assert_error!("two times three four");
+        assert_error!("(1 + 2) 3");
+        assert_error!("2 + 2 )");
+        assert_error!("5 times 6 thanks 7");
+    }
+
+    #[test]
+    fn answer_formats() {
+        let mut session = session();
+        let mut answer = |format: &str, precision: Option<usize>, source: &str| {
+            session.settings.format = format.parse().unwrap();
+            session.settings.precision = precision;
+            session.eval(source).unwrap()
+        };
+
+        assert_eq!(answer("auto", None, "1 / 3"), "0.3333333333333333");
+        assert_eq!(answer("auto", Some(3), "1 / 3"), "0.333");
+        assert_eq!(answer("auto", Some(3), "1 / 4"), "0.25");
+        assert_eq!(answer("fixed", None, "1 / 3"), "0.333333");
+        assert_eq!(answer("fixed", Some(2), "5"), "5.00");
+        assert_eq!(answer("sci", None, "1500"), "1.5e3");
+        assert_eq!(answer("sci", Some(2), "1 / 3"), "3.33e-1");
+        assert_eq!(answer("hex", None, "255"), "0xff");
+        assert_eq!(answer("bin", None, "[5, 2.5]"), "[0b101, 2.5]");
+        assert_eq!(answer("oct", None, "8.0"), "0o10");
+        assert_eq!(answer("auto", Some(1), "5 km"), "5 km");
+        assert!("roman".parse::<Format>().is_err());
+    }
+
+    #[test]
+    fn definitions_are_remembered() {
+        let mut session = session();
+        for line in ["rate = 3", "f(x) = x * rate", "rate = 4", "f(2)"] {
+            session.eval(line).unwrap();
+        }
+
+        assert_eq!(session.variables(), [("rate", "4".to_string())]);
+        assert_eq!(session.functions(), ["f(x) = x * rate"]);
+        assert_eq!(session.history().len(), 4);
+        assert_eq!(session.history()[3].answer, Ok("8".to_string()));
+
+        session.clear();
+        assert!(session.variables().is_empty());
+        assert!(session.functions().is_empty());
+    }
+
+    #[test]
+    fn sessions_are_saved_and_restored() {
+        let path = std::env::temp_dir().join(format!("caldi-session-{}.json", std::process::id()));
+
+        let mut session = session();
+        session.settings.format = Format::Hex;
+        for line in [
+            "budget = 1200 dollars",
+            "trip = 30 km",
+            "grid = [[1, 2], [3, 4]]",
+            "f(x) = 2x + 1",
+            "1 +",
+        ] {
+            let _ = session.eval(line);
+        }
+        session.save(&path).unwrap();
+
+        let mut restored = Session::default();
+        restored.restore(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(restored.variables(), session.variables());
+        assert_eq!(restored.functions(), ["f(x) = 2x + 1"]);
+        assert_eq!(restored.settings.format, Format::Hex);
+        assert_eq!(restored.eval("f(budget / 100 dollars)").unwrap(), "0x19");
+        assert_eq!(restored.eval("trip in m").unwrap(), "30000 m");
+        assert_eq!(restored.history()[4].input, "1 +");
+        assert!(restored.history()[4].answer.is_err());
+    }
+
+    #[test]
+    fn reports() {
+        let mut session = session();
+        for source in [
+            "x = 2",
+            "x * 3 km",
+            "[1, 2.5] + 1",
+            "1 + x +",
+            "f(x) = 2x",
+            "f(1, 2)",
+        ] {
+            let report = serde_json::to_string_pretty(&report(&mut session, source)).unwrap();
+            insta::with_settings!({ description => source }, {
+                assert_display_snapshot!(report)
+            })
+        }
+    }
+
+    #[test]
+    fn unit_errors() {
+        assert_error!("5 km + 3 kg");
+        assert_error!("5 km in s");
+        assert_error!("2 h + 1");
+    }
+
+    #[test]
+    fn scripts() {
+        let script = Script::new(
+            "lib/pay.caldi".into(),
+            "# payroll\nimport \"units.caldi\"\nrate = 25; hours = 40  # a week\n\n  gross(h) = h * rate ;\nimport units".into(),
+        );
+        let statements = script.statements().collect::<Vec<_>>();
+
+        let located = statements
+            .iter()
+            .map(|s| (s.source, s.line, s.column))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            located,
+            [
+                ("import \"units.caldi\"", 2, 1),
+                ("rate = 25", 3, 1),
+                ("hours = 40", 3, 12),
+                ("gross(h) = h * rate", 5, 3),
+                ("import units", 6, 1),
+            ]
+        );
+
+        assert_eq!(statements[0].import().unwrap().unwrap(), "units.caldi");
+        assert!(statements[1].import().is_none());
+        assert_eq!(
+            script.resolve("units.caldi"),
+            std::path::Path::new("lib/units.caldi")
+        );
+
+        let error = statements[4].import().unwrap().unwrap_err();
+        assert_display_snapshot!(render_script_error(error, &statements[4]));
+
+        let mut session = session();
+        let error = session.eval("hours * * 2").unwrap_err();
+        let statement = script::Statement {
+            source: "hours * * 2",
+            ..script.statements().nth(2).unwrap()
+        };
+        assert_display_snapshot!(render_script_error(error, &statement));
+    }
+}
@@ -0,0 +1,315 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+use super::value::{OperandError, Result, Value};
+
+/// Rows by columns.
+pub type Shape = (usize, usize);
+
+/// Pivots smaller than this are taken to be zero, and the matrix singular.
+const EPSILON: f64 = 1e-12;
+
+/// A rectangular grid of numbers, stored a row at a time.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Matrix {
+    rows: usize,
+    cols: usize,
+    entries: Vec<f64>,
+}
+
+impl Matrix {
+    /// Reads a list of equally long, non empty lists of numbers as the rows of
+    /// a matrix.
+    pub fn from_rows(items: &[Value]) -> Option<Self> {
+        let first = match items.first()? {
+            Value::List(row) if !row.is_empty() => row.len(),
+            _ => return None,
+        };
+
+        let mut entries = Vec::with_capacity(items.len() * first);
+
+        for item in items {
+            match item {
+                Value::List(row) if row.len() == first && row.iter().all(Value::is_number) => {
+                    entries.extend(row.iter().map(Value::as_f64))
+                }
+                _ => return None,
+            }
+        }
+
+        Some(Self {
+            rows: items.len(),
+            cols: first,
+            entries,
+        })
+    }
+
+    /// A column vector, out of a list of numbers.
+    pub fn column(items: &[Value]) -> Option<Self> {
+        if items.is_empty() || !items.iter().all(Value::is_number) {
+            return None;
+        }
+
+        Some(Self {
+            rows: items.len(),
+            cols: 1,
+            entries: items.iter().map(Value::as_f64).collect(),
+        })
+    }
+
+    fn identity(n: usize) -> Self {
+        let mut identity = Self::zeros(n, n);
+        for i in 0..n {
+            identity.set(i, i, 1.0);
+        }
+        identity
+    }
+
+    fn zeros(rows: usize, cols: usize) -> Self {
+        Self {
+            rows,
+            cols,
+            entries: vec![0.0; rows * cols],
+        }
+    }
+
+    pub fn shape(&self) -> Shape {
+        (self.rows, self.cols)
+    }
+
+    fn get(&self, row: usize, col: usize) -> f64 {
+        self.entries[row * self.cols + col]
+    }
+
+    fn set(&mut self, row: usize, col: usize, entry: f64) {
+        self.entries[row * self.cols + col] = entry;
+    }
+
+    fn swap_rows(&mut self, a: usize, b: usize) {
+        for col in 0..self.cols {
+            self.entries.swap(a * self.cols + col, b * self.cols + col);
+        }
+    }
+
+    fn expect_same_shape(&self, other: &Matrix) -> Result<()> {
+        if self.shape() != other.shape() {
+            return Err(OperandError::ShapeMismatch {
+                left: self.shape(),
+                right: other.shape(),
+            });
+        }
+
+        Ok(())
+    }
+
+    fn expect_square(&self) -> Result<usize> {
+        if self.rows != self.cols {
+            return Err(OperandError::NotSquare {
+                shape: self.shape(),
+            });
+        }
+
+        Ok(self.rows)
+    }
+
+    fn zip_with(mut self, other: &Matrix, f: impl Fn(f64, f64) -> f64) -> Result<Matrix> {
+        self.expect_same_shape(other)?;
+
+        for (entry, other) in self.entries.iter_mut().zip(&other.entries) {
+            *entry = f(*entry, *other);
+        }
+
+        Ok(self)
+    }
+
+    pub(crate) fn add(self, other: &Matrix) -> Result<Matrix> {
+        self.zip_with(other, |a, b| a + b)
+    }
+
+    pub(crate) fn sub(self, other: &Matrix) -> Result<Matrix> {
+        self.zip_with(other, |a, b| a - b)
+    }
+
+    pub fn elementwise_mul(self, other: &Matrix) -> Result<Matrix> {
+        self.zip_with(other, |a, b| a * b)
+    }
+
+    pub fn scale(mut self, factor: f64) -> Matrix {
+        for entry in &mut self.entries {
+            *entry *= factor;
+        }
+        self
+    }
+
+    pub fn mul(&self, other: &Matrix) -> Result<Matrix> {
+        if self.cols != other.rows {
+            return Err(OperandError::ShapeMismatch {
+                left: self.shape(),
+                right: other.shape(),
+            });
+        }
+
+        let mut product = Matrix::zeros(self.rows, other.cols);
+
+        for row in 0..self.rows {
+            for col in 0..other.cols {
+                let dot = (0..self.cols)
+                    .map(|k| self.get(row, k) * other.get(k, col))
+                    .sum();
+                product.set(row, col, dot);
+            }
+        }
+
+        Ok(product)
+    }
+
+    pub fn powi(self, n: isize) -> Result<Matrix> {
+        let size = self.expect_square()?;
+
+        let base = if n < 0 { self.inverse()? } else { self };
+        let mut power = Matrix::identity(size);
+
+        for _ in 0..n.unsigned_abs() {
+            power = power.mul(&base)?;
+        }
+
+        Ok(power)
+    }
+
+    pub fn transpose(&self) -> Matrix {
+        let mut transposed = Matrix::zeros(self.cols, self.rows);
+
+        for row in 0..self.rows {
+            for col in 0..self.cols {
+                transposed.set(col, row, self.get(row, col));
+            }
+        }
+
+        transposed
+    }
+
+    /// Gaussian elimination with partial pivoting, keeping track of the row swaps.
+    pub fn determinant(&self) -> Result<f64> {
+        let size = self.expect_square()?;
+        let mut m = self.clone();
+        let mut determinant = 1.0;
+
+        for col in 0..size {
+            let pivot = (col..size)
+                .max_by(|&a, &b| m.get(a, col).abs().total_cmp(&m.get(b, col).abs()))
+                .expect("there's at least the diagonal to pivot on");
+
+            if m.get(pivot, col).abs() < EPSILON {
+                return Ok(0.0);
+            }
+
+            if pivot != col {
+                m.swap_rows(pivot, col);
+                determinant = -determinant;
+            }
+
+            determinant *= m.get(col, col);
+
+            for row in col + 1..size {
+                let factor = m.get(row, col) / m.get(col, col);
+                for k in col..size {
+                    m.set(row, k, m.get(row, k) - factor * m.get(col, k));
+                }
+            }
+        }
+
+        Ok(determinant)
+    }
+
+    pub fn inverse(&self) -> Result<Matrix> {
+        let size = self.expect_square()?;
+        self.solve(&Matrix::identity(size))
+    }
+
+    /// Finds the `x` for which `self * x` is `b`, by Gauss-Jordan elimination.
+    pub fn solve(&self, b: &Matrix) -> Result<Matrix> {
+        let size = self.expect_square()?;
+
+        if b.rows != size {
+            return Err(OperandError::ShapeMismatch {
+                left: self.shape(),
+                right: b.shape(),
+            });
+        }
+
+        let mut a = self.clone();
+        let mut x = b.clone();
+
+        for col in 0..size {
+            let pivot = (col..size)
+                .max_by(|&p, &q| a.get(p, col).abs().total_cmp(&a.get(q, col).abs()))
+                .expect("there's at least the diagonal to pivot on");
+
+            if a.get(pivot, col).abs() < EPSILON {
+                return Err(OperandError::Singular);
+            }
+
+            a.swap_rows(pivot, col);
+            x.swap_rows(pivot, col);
+
+            let scale = a.get(col, col);
+            for k in 0..size {
+                a.set(col, k, a.get(col, k) / scale);
+            }
+            for k in 0..x.cols {
+                x.set(col, k, x.get(col, k) / scale);
+            }
+
+            for row in (0..size).filter(|&row| row != col) {
+                let factor = a.get(row, col);
+                for k in 0..size {
+                    a.set(row, k, a.get(row, k) - factor * a.get(col, k));
+                }
+                for k in 0..x.cols {
+                    x.set(row, k, x.get(row, k) - factor * x.get(col, k));
+                }
+            }
+        }
+
+        Ok(x)
+    }
+
+    /// The entries of a single column, as a list.
+    pub fn into_list(self) -> Value {
+        self.entries
+            .into_iter()
+            .map(Value::from)
+            .collect::<Vec<_>>()
+            .into()
+    }
+}
+
+impl Display for Matrix {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[")?;
+        for row in 0..self.rows {
+            if row > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "[")?;
+            for col in 0..self.cols {
+                if col > 0 {
+                    write!(f, ", ")?;
+                }
+                // rounding off what elimination leaves behind, like 0.30000000000000004
+                let entry = self.get(row, col);
+                let rounded = (entry * 1e12).round() / 1e12;
+                write!(f, "{}", if rounded == 0.0 { 0.0 } else { rounded })?;
+            }
+            write!(f, "]")?;
+        }
+        write!(f, "]")
+    }
+}
+
+impl From<Matrix> for Value {
+    fn from(value: Matrix) -> Self {
+        Self::Matrix(value)
+    }
+}
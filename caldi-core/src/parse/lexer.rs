@@ -0,0 +1,586 @@
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum TokenKind {
+    Ident,
+    Integer,
+    Float,
+    Minus,
+    Times,
+    ElementTimes,
+    Over,
+    Plus,
+    BitAnd,
+    BitOr,
+    Xor,
+    Not,
+    ShiftLeft,
+    ShiftRight,
+    Less,
+    Greater,
+    LessEqual,
+    GreaterEqual,
+    Equal,
+    NotEqual,
+    If,
+    Then,
+    Else,
+    Power,
+    LParen,
+    RParen,
+    Comma,
+    Assign,
+    Of,
+    And,
+    Define,
+    LBracket,
+    RBracket,
+    Numbers,
+    In,
+    From,
+    HowMany,
+    Between,
+    Colon,
+    Meridiem,
+    /// A sign like `$`, `€` or `£`, written before an amount.
+    CurrencySign,
+    Solve,
+    For,
+    Makes,
+    Derivative,
+    WithRespectTo,
+    Squared,
+    Cubed,
+    Dice,
+    CoinFlip,
+    Eof,
+    Illegal,
+}
+
+#[derive(Debug)]
+pub struct Token<'s> {
+    pub start: usize,
+    pub kind: TokenKind,
+    pub text: &'s str,
+}
+
+pub struct Lexer<'s> {
+    input: &'s str,
+    input_bytes: &'s [u8],
+    position: usize,
+    read_position: usize,
+    tokens: Vec<Token<'s>>,
+}
+
+impl<'s> Lexer<'s> {
+    pub fn new(input: &'s str) -> Self {
+        Self {
+            input,
+            input_bytes: input.as_bytes(),
+            position: 0,
+            read_position: 1,
+            tokens: vec![],
+        }
+    }
+
+    pub fn tokenize(mut self) -> Vec<Token<'s>> {
+        loop {
+            if let Some(token) = self.tokens.last() {
+                if token.kind == TokenKind::Eof {
+                    break;
+                }
+            }
+
+            self.next_token();
+        }
+
+        return self.tokens;
+    }
+
+    fn char(&self) -> Option<char> {
+        self.input_bytes
+            .get(self.position)
+            .map(|&byte| byte as char)
+    }
+
+    fn peek_char(&self) -> Option<char> {
+        self.input_bytes
+            .get(self.read_position)
+            .map(|&byte| byte as char)
+    }
+
+    fn advance(&mut self) {
+        self.position = self.read_position;
+        self.read_position += 1;
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.char().unwrap_or('\0').is_ascii_whitespace() {
+            self.advance();
+        }
+    }
+
+    fn next_token(&mut self) {
+        self.skip_whitespace();
+
+        let c = match self.char() {
+            Some(c) => c,
+            None => {
+                self.tokens.push(self.char_token(TokenKind::Eof));
+                return;
+            }
+        };
+
+        let token = match c {
+            '+' => self.char_token(TokenKind::Plus),
+
+            '-' => self.char_token(TokenKind::Minus),
+
+            '*' => self.char_token(TokenKind::Times),
+
+            '/' => self.char_token(TokenKind::Over),
+
+            '.' if self.peek_char() == Some('*') => self.double_char_token(TokenKind::ElementTimes),
+
+            '&' => self.char_token(TokenKind::BitAnd),
+
+            '|' => self.char_token(TokenKind::BitOr),
+
+            '~' => self.char_token(TokenKind::Not),
+
+            '^' => self.char_token(TokenKind::Power),
+
+            '(' => self.char_token(TokenKind::LParen),
+
+            ')' => self.char_token(TokenKind::RParen),
+
+            ',' => self.char_token(TokenKind::Comma),
+
+            '$' => self.char_token(TokenKind::CurrencySign),
+
+            ':' => self.char_token(TokenKind::Colon),
+
+            '[' => self.char_token(TokenKind::LBracket),
+
+            ']' => self.char_token(TokenKind::RBracket),
+
+            '<' => match self.peek_char() {
+                Some('<') => self.double_char_token(TokenKind::ShiftLeft),
+                Some('=') => self.double_char_token(TokenKind::LessEqual),
+                _ => self.char_token(TokenKind::Less),
+            },
+
+            '>' => match self.peek_char() {
+                Some('>') => self.double_char_token(TokenKind::ShiftRight),
+                Some('=') => self.double_char_token(TokenKind::GreaterEqual),
+                _ => self.char_token(TokenKind::Greater),
+            },
+
+            '=' => match self.peek_char() {
+                Some('=') => self.double_char_token(TokenKind::Equal),
+                _ => self.char_token(TokenKind::Assign),
+            },
+
+            '!' if self.peek_char() == Some('=') => self.double_char_token(TokenKind::NotEqual),
+
+            c if c.is_ascii_digit() => {
+                let start = self.position;
+
+                let mut is_float = false;
+
+                while self.peek_char().unwrap_or('\0').is_ascii_digit() {
+                    self.advance();
+                }
+
+                if self.peek_char().unwrap_or('\0') == '.' {
+                    is_float = true;
+                    self.advance();
+                }
+
+                while self.peek_char().unwrap_or('\0').is_ascii_digit() {
+                    self.advance();
+                }
+
+                // the number of dice rolled, as in "3d6"
+                if !is_float && self.at_dice(self.read_position) {
+                    self.advance();
+                    while self.peek_char().unwrap_or('\0').is_ascii_digit() {
+                        self.advance();
+                    }
+
+                    self.tokens.push(Token {
+                        start,
+                        kind: TokenKind::Dice,
+                        text: &self.input[start..=self.position],
+                    });
+                    self.advance();
+                    return;
+                }
+
+                let end = self.position;
+
+                let string = &self.input[start..=end];
+
+                // the "rd" of "3rd" says nothing more about the number
+                if !is_float && self.at_ordinal_suffix() {
+                    self.advance();
+                    self.advance();
+                }
+
+                if is_float {
+                    Token {
+                        start,
+                        kind: TokenKind::Float,
+                        text: string,
+                    }
+                } else {
+                    Token {
+                        start,
+                        kind: TokenKind::Integer,
+                        text: string,
+                    }
+                }
+            }
+
+            'a' | 'p' | 'A' | 'P' if self.at_dotted_meridiem() => {
+                let start = self.position;
+                for _ in 0..3 {
+                    self.advance();
+                }
+
+                Token {
+                    start,
+                    kind: TokenKind::Meridiem,
+                    text: &self.input[start..=self.position],
+                }
+            }
+
+            'd' if self.at_derivative() => {
+                let start = self.position;
+                self.advance();
+                self.advance();
+
+                while self.peek_char().is_some_and(|c| c.is_ascii_alphanumeric()) {
+                    self.advance();
+                }
+
+                Token {
+                    start,
+                    kind: TokenKind::Derivative,
+                    text: &self.input[start..=self.position],
+                }
+            }
+
+            'd' if self.at_dice(self.position) => {
+                let start = self.position;
+                while self.peek_char().unwrap_or('\0').is_ascii_digit() {
+                    self.advance();
+                }
+
+                Token {
+                    start,
+                    kind: TokenKind::Dice,
+                    text: &self.input[start..=self.position],
+                }
+            }
+
+            c if c.is_ascii_alphabetic() => {
+                let start = self.position;
+
+                // apostrophes keep contractions like "what's" together
+                while self
+                    .peek_char()
+                    .map(|c| c.is_ascii_alphabetic() || c == ' ' || c == '\'')
+                    .unwrap_or(false)
+                    && !self.at_dice(self.read_position)
+                {
+                    self.advance();
+                }
+
+                let end = self.position;
+
+                self.push_words(start, end);
+                self.advance();
+                return;
+            }
+
+            _ => match self.illegal_token() {
+                token if token.text.chars().all(is_currency_sign) => Token {
+                    kind: TokenKind::CurrencySign,
+                    ..token
+                },
+                token => token,
+            },
+        };
+
+        self.tokens.push(token);
+
+        self.advance();
+    }
+
+    /// Splits a run of words into tokens, preferring the longest spoken phrase
+    /// (e.g. "shifted left by") that names an operator at each word.
+    fn push_words(&mut self, start: usize, end: usize) {
+        let mut words = vec![];
+        let mut offset = start;
+
+        for word in self.input[start..=end].split(' ') {
+            if !word.is_empty() {
+                words.push((offset, word));
+            }
+            offset += word.len() + 1;
+        }
+
+        let mut i = 0;
+
+        while i < words.len() {
+            let (word_start, word) = words[i];
+
+            let phrase = (2..=MAX_PHRASE_WORDS.min(words.len() - i))
+                .rev()
+                .find_map(|len| {
+                    let (last_start, last) = words[i + len - 1];
+                    let text = &self.input[word_start..last_start + last.len()];
+                    let phrase = text.split_whitespace().collect::<Vec<_>>().join(" ");
+
+                    keyword(&phrase).map(|kind| (len, kind, text))
+                });
+
+            let (len, kind, text) =
+                phrase.unwrap_or_else(|| (1, keyword(word).unwrap_or(TokenKind::Ident), word));
+
+            self.tokens.push(Token {
+                start: word_start,
+                kind,
+                text,
+            });
+
+            i += len;
+        }
+    }
+
+    fn at_ordinal_suffix(&self) -> bool {
+        let rest = &self.input_bytes[self.read_position..];
+
+        match rest {
+            [a, b, after @ ..] => {
+                let suffix = [a.to_ascii_lowercase(), b.to_ascii_lowercase()];
+                matches!(&suffix, b"st" | b"nd" | b"rd" | b"th")
+                    && !after.first().is_some_and(u8::is_ascii_alphabetic)
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether the "a.m." or "p.m." of a time is next.
+    fn at_dotted_meridiem(&self) -> bool {
+        let rest = &self.input_bytes[self.position..];
+
+        rest.len() >= 4 && rest[1..4].eq_ignore_ascii_case(b".m.")
+    }
+
+    /// Whether a derivative like "d/dx" is next.
+    fn at_derivative(&self) -> bool {
+        let rest = &self.input_bytes[self.position..];
+
+        rest.len() >= 4 && rest.starts_with(b"d/d") && rest[3].is_ascii_alphabetic()
+    }
+
+    /// Whether dice like the "d20" of "roll a d20", or the "d6" of "3d6", are at `position`.
+    fn at_dice(&self, position: usize) -> bool {
+        let bytes = self.input_bytes;
+        let starts_word = position == 0 || !bytes[position - 1].is_ascii_alphabetic();
+        let sides = bytes
+            .get(position + 1..)
+            .unwrap_or_default()
+            .iter()
+            .take_while(|b| b.is_ascii_digit())
+            .count();
+
+        starts_word
+            && bytes.get(position) == Some(&b'd')
+            && sides > 0
+            && !bytes
+                .get(position + 1 + sides)
+                .is_some_and(|b| b.is_ascii_alphanumeric() || *b == b'.')
+    }
+
+    fn double_char_token(&mut self, kind: TokenKind) -> Token<'s> {
+        let start = self.position;
+        self.advance();
+
+        Token {
+            start,
+            kind,
+            text: &self.input[start..=self.position],
+        }
+    }
+
+    /// A character that means nothing here, all of it if it takes more than a byte.
+    fn illegal_token(&mut self) -> Token<'s> {
+        let start = self.position;
+
+        while self
+            .input_bytes
+            .get(self.read_position)
+            .is_some_and(|byte| byte & 0b1100_0000 == 0b1000_0000)
+        {
+            self.advance();
+        }
+
+        Token {
+            start,
+            kind: TokenKind::Illegal,
+            text: &self.input[start..=self.position],
+        }
+    }
+
+    fn char_token(&self, kind: TokenKind) -> Token<'s> {
+        return Token {
+            start: self.position,
+            kind,
+            text: self
+                .input
+                .get(self.position..self.position + 1)
+                .unwrap_or_default(),
+        };
+    }
+}
+
+/// Whether `c` is a currency's sign, whether or not caldi knows the currency, so
+/// one that it doesn't is an error rather than skipped like other symbols.
+fn is_currency_sign(c: char) -> bool {
+    matches!(c, '¢' | '£' | '¤' | '¥' | '\u{20a0}'..='\u{20cf}')
+}
+
+/// The most words any spoken operator is made of.
+const MAX_PHRASE_WORDS: usize = 6;
+
+fn keyword(phrase: &str) -> Option<TokenKind> {
+    let kind = match phrase {
+        _ if number_word(phrase).is_some() => TokenKind::Integer,
+        "plus" => TokenKind::Plus,
+        "minus" | "negative" => TokenKind::Minus,
+        "times" | "x" | "multiplied by" => TokenKind::Times,
+        "over" | "divided by" => TokenKind::Over,
+        "elementwise times" | "element wise times" => TokenKind::ElementTimes,
+        "bitwise and" => TokenKind::BitAnd,
+        "bitwise or" => TokenKind::BitOr,
+        "xor" | "bitwise xor" | "exclusive or" => TokenKind::Xor,
+        "not" | "bitwise not" => TokenKind::Not,
+        "shifted left by" | "shift left by" | "left shift" => TokenKind::ShiftLeft,
+        "shifted right by" | "shift right by" | "right shift" => TokenKind::ShiftRight,
+        "less than" | "is less than" | "fewer than" => TokenKind::Less,
+        "greater than" | "is greater than" | "more than" | "is more than" => TokenKind::Greater,
+        "less than or equal to" | "is less than or equal to" | "at most" | "is at most" => {
+            TokenKind::LessEqual
+        }
+        "greater than or equal to" | "is greater than or equal to" | "at least" | "is at least" => {
+            TokenKind::GreaterEqual
+        }
+        "equals" | "equal" | "equal to" | "is equal to" => TokenKind::Equal,
+        "not equal to" | "is not equal to" | "does not equal" => TokenKind::NotEqual,
+        "if" => TokenKind::If,
+        "then" => TokenKind::Then,
+        "else" | "otherwise" => TokenKind::Else,
+        "to the power of" | "raised to" | "raised to the power of" => TokenKind::Power,
+        "of" => TokenKind::Of,
+        "and" => TokenKind::And,
+        "define" | "let" => TokenKind::Define,
+        "as" | "be" => TokenKind::Assign,
+        "numbers" | "the numbers" => TokenKind::Numbers,
+        "in" | "to" | "into" => TokenKind::In,
+        "from" | "after" => TokenKind::From,
+        "how many" => TokenKind::HowMany,
+        "between" => TokenKind::Between,
+        "solve" => TokenKind::Solve,
+        "for" => TokenKind::For,
+        "makes" | "make" => TokenKind::Makes,
+        "derivative of" | "the derivative of" => TokenKind::Derivative,
+        "with respect to" => TokenKind::WithRespectTo,
+        "squared" => TokenKind::Squared,
+        "cubed" => TokenKind::Cubed,
+        "flip a coin" | "toss a coin" | "coin flip" | "a coin flip" | "heads or tails" => {
+            TokenKind::CoinFlip
+        }
+        "am" | "pm" | "AM" | "PM" => TokenKind::Meridiem,
+        // names of built in functions and units that take more than a word to say
+        "standard deviation"
+        | "square root"
+        | "nautical mile"
+        | "nautical miles"
+        | "fluid ounce"
+        | "fluid ounces"
+        | "degrees celsius"
+        | "degrees fahrenheit"
+        | "miles per hour"
+        | "kilometers per hour"
+        | "kilometres per hour"
+        | "pound force"
+        | "pounds force"
+        | "watt hour"
+        | "watt hours"
+        | "kilowatt hour"
+        | "kilowatt hours"
+        | "us dollar"
+        | "us dollars"
+        | "canadian dollar"
+        | "canadian dollars"
+        | "australian dollar"
+        | "australian dollars"
+        | "british pound"
+        | "british pounds"
+        | "pound sterling"
+        | "pounds sterling"
+        | "swiss franc"
+        | "swiss francs"
+        | "mexican peso"
+        | "mexican pesos" => TokenKind::Ident,
+        _ => return None,
+    };
+
+    Some(kind)
+}
+
+/// The number said with a word like "seven", or two like "forty two".
+pub fn number_word(phrase: &str) -> Option<isize> {
+    const ONES: [&str; 20] = [
+        "zero",
+        "one",
+        "two",
+        "three",
+        "four",
+        "five",
+        "six",
+        "seven",
+        "eight",
+        "nine",
+        "ten",
+        "eleven",
+        "twelve",
+        "thirteen",
+        "fourteen",
+        "fifteen",
+        "sixteen",
+        "seventeen",
+        "eighteen",
+        "nineteen",
+    ];
+    const TENS: [&str; 8] = [
+        "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety",
+    ];
+
+    let ones = |word| ONES.iter().position(|&one| one == word);
+    let tens = |word| {
+        TENS.iter()
+            .position(|&ten| ten == word)
+            .map(|i| (i + 2) * 10)
+    };
+
+    let number = match phrase.split_once(' ') {
+        None => ones(phrase).or_else(|| tens(phrase))?,
+        Some((ten, one)) => tens(ten)? + ones(one).filter(|one| (1..10).contains(one))?,
+    };
+
+    Some(number as isize)
+}
@@ -0,0 +1,2120 @@
+use self::{
+    ast::{
+        BinOp, BinaryExpr, Call, DateLiteral, Derivative, Dice, Equation, Expr, FunctionDef,
+        IfExpr, Iterated, Iteration, Span, Statement, TimeLiteral, UnaryExpr, Variable,
+    },
+    lexer::{number_word, Lexer, Token, TokenKind},
+};
+
+pub mod lexer;
+
+use super::{calendar, constant, currency, units};
+
+/// Reads a problem, in words or symbols, into an [`ast::Expr`] or [`ast::Statement`].
+pub struct Parser<'s> {
+    tokens: Vec<Token<'s>>,
+    position: usize,
+    read_position: usize,
+}
+
+/// What parsing or evaluating comes to, with the error pinned to where it is in
+/// the source.
+pub type Result<T> = std::result::Result<T, error::ErrorKind>;
+
+impl<'s> Parser<'s> {
+    pub fn new(input: &'s str) -> Self {
+        Self {
+            tokens: Lexer::new(input).tokenize(),
+            position: 0,
+            read_position: 1,
+        }
+    }
+
+    fn token(&self) -> Option<&Token<'s>> {
+        if let Some(t) = self.tokens.get(self.position) {
+            if t.kind == TokenKind::Eof {
+                return None;
+            }
+
+            return Some(t);
+        }
+
+        return None;
+    }
+
+    fn peek_token(&self) -> Option<&Token<'s>> {
+        if let Some(t) = self.tokens.get(self.read_position) {
+            if t.kind == TokenKind::Eof {
+                return None;
+            }
+
+            return Some(t);
+        }
+
+        return None;
+    }
+
+    fn token_start(&self) -> usize {
+        self.token().map(|t| t.start).unwrap_or_default()
+    }
+
+    /// From `start` up to the end of the current token.
+    fn span_from(&self, start: usize) -> Span {
+        let end = self
+            .token()
+            .map(|t| t.start + t.text.len())
+            .unwrap_or(start);
+        Span { start, end }
+    }
+
+    fn peek_second_token(&self) -> Option<&Token<'s>> {
+        self.tokens
+            .get(self.read_position + 1)
+            .filter(|t| t.kind != TokenKind::Eof)
+    }
+
+    fn advance(&mut self) {
+        self.position = self.read_position;
+        self.read_position += 1;
+    }
+
+    /// Parses an expression, leaving whatever follows it.
+    pub fn parse(&mut self) -> Result<Expr> {
+        self.parse_expr(Precedence::default())
+    }
+
+    /// Parses either a definition of a variable or function, or a plain expression,
+    /// which has to be all there is to the input.
+    pub fn parse_statement(&mut self) -> Result<Statement> {
+        let statement = self.parse_definition_or_expr()?;
+
+        // Anything left over would otherwise be silently ignored, like the 3 of `2 3`
+        // was, except for punctuation ending a sentence, like the "?" of "what is 2 + 2?",
+        // and words said after the problem, like the "please" of "2 plus 3, please"
+        let mut rest = self.tokens[self.read_position..]
+            .iter()
+            .filter(|t| t.kind != TokenKind::Eof);
+
+        match rest.find(|t| !is_trailing_filler(t)) {
+            Some(token) => Err(error::ErrorKind::UnexpectedToken {
+                token: token.into(),
+            }),
+            None => Ok(statement),
+        }
+    }
+
+    fn parse_definition_or_expr(&mut self) -> Result<Statement> {
+        let token = match self.token() {
+            Some(token) => token,
+            None => return Err(error::ErrorKind::UnexpectedEnd { at: 0 }),
+        };
+
+        if token.kind == TokenKind::Define {
+            return self.parse_spoken_definition();
+        }
+
+        if is_name(token) && self.peek_token().map(|t| t.kind) == Some(TokenKind::Assign) {
+            let name = token.text.to_string();
+            self.advance();
+            self.advance();
+
+            return Ok(Statement::Assign {
+                name,
+                value: self.parse()?,
+            });
+        }
+
+        if self.is_function_definition() {
+            return self.parse_function_definition();
+        }
+
+        Ok(Statement::Expr(self.parse()?))
+    }
+
+    fn parse_expr(&mut self, curr_precedence: Precedence) -> Result<Expr> {
+        let start = self.token_start();
+
+        let mut exp = match self.token() {
+            Some(token) => match token.kind {
+                _ if is_name(token)
+                    && self.peek_token().map(|t| t.kind) == Some(TokenKind::Makes) =>
+                {
+                    self.parse_spoken_equation()?
+                }
+                TokenKind::Ident | TokenKind::Integer
+                    if (matches!(token.text, "a" | "an") || token.kind == TokenKind::Integer)
+                        && self.peek_token().is_some_and(|t| {
+                            t.kind == TokenKind::Dice && t.text.starts_with('d')
+                        }) =>
+                {
+                    self.parse_dice()?
+                }
+                TokenKind::Ident
+                    if is_month(token)
+                        && self.peek_token().map(|t| t.kind) == Some(TokenKind::Integer) =>
+                {
+                    self.parse_date()?
+                }
+                TokenKind::Ident => match self.peek_token() {
+                    Some(t) if matches!(t.kind, TokenKind::LParen | TokenKind::Of) => {
+                        self.parse_call()?
+                    }
+                    Some(t) if starts_operand(t) && !self.follows_operand() => {
+                        self.advance(); // skipping identifiers used as filler words
+                        self.parse()?
+                    }
+                    _ => self.parse_variable(),
+                },
+                TokenKind::Times if is_name(token) => self.parse_variable(),
+                TokenKind::LParen => self.parse_grouped_expr()?,
+                TokenKind::LBracket => self.parse_list()?,
+                TokenKind::Numbers => self.parse_spoken_list()?,
+                TokenKind::CurrencySign => self.parse_currency_amount()?,
+                TokenKind::Float => {
+                    let number = self.parse_fp_number();
+                    self.parse_quantity(number)?
+                }
+                TokenKind::Integer => match self.peek_token() {
+                    Some(t) if matches!(t.kind, TokenKind::Colon | TokenKind::Meridiem) => {
+                        self.parse_time()?
+                    }
+                    Some(t)
+                        if is_month(t)
+                            || (t.kind == TokenKind::Of
+                                && self.peek_second_token().is_some_and(is_month)) =>
+                    {
+                        self.parse_day_first_date()?
+                    }
+                    _ => {
+                        let number = self.parse_integer();
+                        self.parse_quantity(number)?
+                    }
+                },
+                TokenKind::HowMany => self.parse_how_many()?,
+                TokenKind::Solve => match self.peek_token() {
+                    Some(t) if matches!(t.kind, TokenKind::LParen | TokenKind::Of) => {
+                        self.parse_call()?
+                    }
+                    _ => self.parse_equation()?,
+                },
+                TokenKind::Derivative => self.parse_derivative()?,
+                TokenKind::Dice => self.parse_dice()?,
+                TokenKind::CoinFlip => Expr::CoinFlip,
+                TokenKind::Plus => self.parse_unary_expr()?,
+                TokenKind::Minus => self.parse_unary_expr()?,
+                TokenKind::Not => self.parse_unary_expr()?,
+                TokenKind::If => self.parse_if_expr()?,
+                TokenKind::Times
+                | TokenKind::ElementTimes
+                | TokenKind::Over
+                | TokenKind::BitAnd
+                | TokenKind::BitOr
+                | TokenKind::Xor
+                | TokenKind::ShiftLeft
+                | TokenKind::ShiftRight
+                | TokenKind::Less
+                | TokenKind::Greater
+                | TokenKind::LessEqual
+                | TokenKind::GreaterEqual
+                | TokenKind::Equal
+                | TokenKind::NotEqual
+                | TokenKind::Then
+                | TokenKind::Else
+                | TokenKind::Power
+                | TokenKind::RParen
+                | TokenKind::Comma
+                | TokenKind::Assign
+                | TokenKind::Of
+                | TokenKind::And
+                | TokenKind::Define
+                | TokenKind::RBracket
+                | TokenKind::In
+                | TokenKind::From
+                | TokenKind::Between
+                | TokenKind::Colon
+                | TokenKind::Meridiem
+                | TokenKind::For
+                | TokenKind::Makes
+                | TokenKind::WithRespectTo
+                | TokenKind::Squared
+                | TokenKind::Cubed => {
+                    return Err(error::ErrorKind::UnexpectedToken {
+                        token: token.into(),
+                    })
+                }
+                TokenKind::Eof => return Err(error::ErrorKind::UnexpectedEnd { at: token.start }),
+                TokenKind::Illegal => {
+                    self.advance(); // skipping any illegal characters
+                    self.parse()?
+                }
+            },
+            None => return Err(error::ErrorKind::UnexpectedEnd { at: 0 }),
+        };
+
+        loop {
+            // "k squared", which binds as tightly as `^` does
+            if let Some(exponent) = self.peek_token().and_then(spoken_power) {
+                if curr_precedence < Precedence::Power {
+                    let left = self.span_from(start);
+                    self.advance();
+
+                    exp = Expr::BinExpr(Box::new(BinaryExpr {
+                        left: exp,
+                        op: BinOp::Power,
+                        right: Expr::Integer(exponent),
+                        at: self.token_start(),
+                        operands: [left, self.span_from(self.token_start())],
+                    }));
+                    continue;
+                }
+            }
+
+            // Operands side by side, as in `2 y` or `3 (4 + 5)`, are multiplied as
+            // tightly as with `*`, so `6 / 2 y` is 3y. Written right against a
+            // number, as in `6 / 2y`, they're taken together first, which is up to
+            // `parse_quantity`. A number after an operand, as in `2 3`, could be
+            // meant a few ways, so it's left to be written out.
+            if let Some(token) = self.peek_token().filter(|_| self.at_juxtaposition()) {
+                if matches!(token.kind, TokenKind::Integer | TokenKind::Float) {
+                    return Err(error::ErrorKind::AmbiguousJuxtaposition { at: token.start });
+                }
+
+                if curr_precedence >= Precedence::Product {
+                    break;
+                }
+
+                let left = self.span_from(start);
+                self.advance();
+                let at = self.token_start();
+
+                exp = Expr::BinExpr(Box::new(BinaryExpr {
+                    left: exp,
+                    op: BinOp::Times,
+                    right: self.parse_expr(Precedence::Product)?,
+                    at,
+                    operands: [left, self.span_from(at)],
+                }));
+                continue;
+            }
+
+            let peek_precedence: Precedence = match self.peek_token().map(|t| t.try_into()) {
+                Some(Ok(p)) => p,
+                Some(Err(_)) => return Ok(exp),
+                None => return Ok(exp),
+            };
+
+            if self
+                .peek_token()
+                .map(|t| t.kind != TokenKind::Eof)
+                .unwrap_or(false)
+                && curr_precedence < peek_precedence
+            {
+                let left = self.span_from(start);
+                self.advance();
+                exp = self.parse_binary_expr(exp, left)?;
+            } else {
+                break;
+            }
+        }
+
+        Ok(exp)
+    }
+
+    /// Whether the next token starts another operand, rather than an operator. An
+    /// "x" is taken as a name only if nothing it could multiply comes after it.
+    fn at_juxtaposition(&self) -> bool {
+        if self.at_trailing_filler() {
+            return false;
+        }
+
+        match self.peek_token() {
+            Some(token) if token.kind == TokenKind::Times => {
+                is_name(token) && !self.peek_second_token().is_some_and(starts_operand)
+            }
+            Some(token) => {
+                is_name(token)
+                    || matches!(
+                        token.kind,
+                        TokenKind::Integer | TokenKind::Float | TokenKind::LParen
+                    )
+            }
+            None => false,
+        }
+    }
+
+    /// Whether all that's left are words said after the problem, like the "thanks"
+    /// of "5 times 6 thanks", rather than more of it.
+    fn at_trailing_filler(&self) -> bool {
+        let mut rest = self.tokens[self.read_position..]
+            .iter()
+            .filter(|t| t.kind != TokenKind::Eof)
+            .peekable();
+
+        rest.peek().is_some() && rest.all(is_trailing_filler)
+    }
+
+    /// Whether the token before this one ends an operand, in which case this one
+    /// is another operand juxtaposed with it rather than a filler word.
+    fn follows_operand(&self) -> bool {
+        let previous = match self.position.checked_sub(1) {
+            Some(position) => &self.tokens[position],
+            None => return false,
+        };
+
+        matches!(
+            previous.kind,
+            TokenKind::Integer
+                | TokenKind::Float
+                | TokenKind::RParen
+                | TokenKind::RBracket
+                | TokenKind::Dice
+                | TokenKind::CoinFlip
+                | TokenKind::Squared
+                | TokenKind::Cubed
+        ) || (previous.kind == TokenKind::Times && is_name(previous))
+    }
+
+    fn parse_unary_expr(&mut self) -> Result<Expr> {
+        let (op, at): (ast::UnOp, usize) = match self.token().map(|t| (t.try_into(), t.start)) {
+            Some((Ok(op), at)) => (op, at),
+            Some((Err(token), _)) => {
+                return Err(error::ErrorKind::UnexpectedToken {
+                    token: token.into(),
+                })
+            }
+            None => return Err(error::ErrorKind::UnexpectedEnd { at: 0 }),
+        };
+
+        self.advance();
+
+        let number = self.parse_expr(Precedence::Prefix)?;
+        Ok(Expr::UnExpr(Box::new(UnaryExpr {
+            op,
+            right: number,
+            at,
+        })))
+    }
+
+    fn parse_binary_expr(&mut self, left: Expr, left_span: Span) -> Result<Expr> {
+        let (op, at): (BinOp, usize) = match self.token().map(|t| (t.try_into(), t.start)) {
+            Some((Ok(op), at)) => (op, at),
+            Some((Err(token), _)) => {
+                return Err(error::ErrorKind::UnexpectedToken {
+                    token: token.into(),
+                })
+            }
+            None => return Err(error::ErrorKind::UnexpectedEnd { at: 1 }),
+        };
+
+        self.advance();
+
+        let precedence = match op {
+            // right associative, so that a following `^` binds into the right hand side
+            BinOp::Power => Precedence::Prefix,
+            op => op.into(),
+        };
+
+        let start = self.token_start();
+        let right = self.parse_expr(precedence)?;
+
+        Ok(Expr::BinExpr(Box::new(BinaryExpr {
+            left,
+            op,
+            right,
+            at,
+            operands: [left_span, self.span_from(start)],
+        })))
+    }
+
+    fn parse_grouped_expr(&mut self) -> Result<Expr> {
+        self.advance();
+        let expr = self.parse_expr(Precedence::None)?;
+        self.expect_peek(TokenKind::RParen)?;
+
+        Ok(expr)
+    }
+
+    fn parse_list(&mut self) -> Result<Expr> {
+        let mut items = vec![];
+
+        if self.peek_token().map(|t| t.kind) == Some(TokenKind::RBracket) {
+            self.advance();
+            return Ok(Expr::List(items));
+        }
+
+        loop {
+            self.advance();
+            items.push(self.parse_expr(Precedence::None)?);
+
+            if self.peek_token().map(|t| t.kind) == Some(TokenKind::Comma) {
+                self.advance();
+            } else {
+                self.expect_peek(TokenKind::RBracket)?;
+                break;
+            }
+        }
+
+        Ok(Expr::List(items))
+    }
+
+    /// Parses something like "the numbers 4, 8 and 15".
+    fn parse_spoken_list(&mut self) -> Result<Expr> {
+        let mut items = vec![];
+
+        loop {
+            self.advance();
+            items.push(self.parse_expr(Precedence::Sum)?);
+
+            match self.peek_token().map(|t| t.kind) {
+                Some(TokenKind::Comma | TokenKind::And) => self.advance(),
+                _ => break,
+            }
+        }
+
+        Ok(Expr::List(items))
+    }
+
+    /// Attaches the unit following a number, as in `5 km` or `20 m^2`, and
+    /// adds up quantities said in parts, like "3 feet 2 inches". Imaginary
+    /// numbers, like `3i`, are attached to their unit here too.
+    fn parse_quantity(&mut self, number: Expr) -> Result<Expr> {
+        let number_span = self.span_from(self.token_start());
+
+        if self
+            .peek_token()
+            .is_some_and(|t| t.kind == TokenKind::Ident && matches!(t.text, "i" | "j"))
+        {
+            self.advance();
+
+            return Ok(Expr::BinExpr(Box::new(BinaryExpr {
+                left: number,
+                op: BinOp::Times,
+                at: self.token_start(),
+                right: self.parse_variable(),
+                operands: [number_span, self.span_from(self.token_start())],
+            })));
+        }
+
+        // a coefficient written right against its unknown, as in `3x`, unless it's
+        // something like `2x3`
+        let coefficient = self
+            .peek_token()
+            .is_some_and(|t| is_name(t) && !is_unit(t) && t.start == number_span.end)
+            && !matches!(
+                self.peek_second_token().map(|t| t.kind),
+                Some(TokenKind::Integer | TokenKind::Float)
+            );
+
+        if coefficient {
+            self.advance();
+            let at = self.token_start();
+
+            return Ok(Expr::BinExpr(Box::new(BinaryExpr {
+                left: number,
+                op: BinOp::Times,
+                right: self.parse_expr(Precedence::Product)?,
+                at,
+                operands: [number_span, self.span_from(at)],
+            })));
+        }
+
+        let unit = match self.peek_token() {
+            Some(token) if is_unit(token) => Variable {
+                name: token.text.to_string(),
+                at: token.start,
+            },
+            _ => return Ok(number),
+        };
+
+        self.advance();
+
+        let at = unit.at;
+        let mut unit = Expr::Unit(unit);
+
+        if self.peek_token().map(|t| t.kind) == Some(TokenKind::Power)
+            && self.peek_second_token().map(|t| t.kind) == Some(TokenKind::Integer)
+        {
+            let unit_span = self.span_from(at);
+            self.advance();
+            let at = self.token_start();
+            self.advance();
+
+            unit = Expr::BinExpr(Box::new(BinaryExpr {
+                left: unit,
+                op: BinOp::Power,
+                right: self.parse_integer(),
+                at,
+                operands: [unit_span, self.span_from(self.token_start())],
+            }));
+        } else if let Some(exponent) = self.peek_token().and_then(spoken_power) {
+            // "5 meters squared" is an area, rather than the square of a length
+            let unit_span = self.span_from(at);
+            self.advance();
+
+            unit = Expr::BinExpr(Box::new(BinaryExpr {
+                left: unit,
+                op: BinOp::Power,
+                right: Expr::Integer(exponent),
+                at: self.token_start(),
+                operands: [unit_span, self.span_from(self.token_start())],
+            }));
+        }
+
+        let quantity = Expr::BinExpr(Box::new(BinaryExpr {
+            left: number,
+            op: BinOp::Times,
+            right: unit,
+            at,
+            operands: [number_span, self.span_from(at)],
+        }));
+
+        let continues = matches!(
+            self.peek_token().map(|t| t.kind),
+            Some(TokenKind::Integer | TokenKind::Float)
+        ) && self.peek_second_token().is_some_and(is_unit);
+
+        if !continues {
+            return Ok(quantity);
+        }
+
+        let quantity_span = self.span_from(number_span.start);
+        self.advance();
+
+        let at = self.token_start();
+        let number = match self.token().unwrap().kind {
+            TokenKind::Float => self.parse_fp_number(),
+            _ => self.parse_integer(),
+        };
+
+        Ok(Expr::BinExpr(Box::new(BinaryExpr {
+            left: quantity,
+            op: BinOp::Plus,
+            right: self.parse_quantity(number)?,
+            at,
+            operands: [quantity_span, self.span_from(at)],
+        })))
+    }
+
+    /// An amount of money written the usual way, as in `$20` or `€15`.
+    fn parse_currency_amount(&mut self) -> Result<Expr> {
+        let sign = self.token().unwrap();
+        let at = sign.start;
+        if currency::lookup(sign.text).is_none() {
+            return Err(error::ErrorKind::UnknownCurrency {
+                sign: sign.text.to_string(),
+                at,
+            });
+        }
+        let unit = Expr::Unit(Variable {
+            name: sign.text.to_string(),
+            at,
+        });
+
+        let sign_span = self.span_from(at);
+        self.advance();
+
+        let start = self.token_start();
+        let amount = self.parse_expr(Precedence::Prefix)?;
+
+        Ok(Expr::BinExpr(Box::new(BinaryExpr {
+            left: amount,
+            op: BinOp::Times,
+            right: unit,
+            at,
+            operands: [self.span_from(start), sign_span],
+        })))
+    }
+
+    /// A date said month first, as in "March 3rd" or "June 1 2024".
+    fn parse_date(&mut self) -> Result<Expr> {
+        let token = self.token().unwrap();
+        let at = token.start;
+        let month = calendar::month(token.text).expect("checked to be a month before parsing");
+
+        self.advance();
+        let day =
+            clock_number(self.token().unwrap()).ok_or(error::ErrorKind::InvalidDate { at })?;
+
+        Ok(Expr::Date(DateLiteral {
+            year: self.parse_year(),
+            month,
+            day,
+            at,
+        }))
+    }
+
+    /// A date said day first, as in "the 3rd of March".
+    fn parse_day_first_date(&mut self) -> Result<Expr> {
+        let token = self.token().unwrap();
+        let at = token.start;
+        let day = clock_number(token).ok_or(error::ErrorKind::InvalidDate { at })?;
+
+        if self.peek_token().map(|t| t.kind) == Some(TokenKind::Of) {
+            self.advance();
+        }
+
+        self.advance();
+        let month = calendar::month(self.token().unwrap().text)
+            .expect("checked to be a month before parsing");
+
+        Ok(Expr::Date(DateLiteral {
+            year: self.parse_year(),
+            month,
+            day,
+            at,
+        }))
+    }
+
+    /// The year at the end of a date, if it's been said.
+    fn parse_year(&mut self) -> Option<i32> {
+        let year = self
+            .peek_token()
+            .filter(|t| t.kind == TokenKind::Integer && t.text.len() == 4)?
+            .text
+            .parse()
+            .ok()?;
+
+        self.advance();
+        Some(year)
+    }
+
+    /// A time of day, like "2:45 pm", "14:30" or "9 am".
+    fn parse_time(&mut self) -> Result<Expr> {
+        let token = self.token().unwrap();
+        let at = token.start;
+        let mut hour = clock_number(token).ok_or(error::ErrorKind::InvalidTime { at })?;
+        let mut minute: u32 = 0;
+
+        if self.peek_token().map(|t| t.kind) == Some(TokenKind::Colon) {
+            self.advance();
+            self.expect_peek(TokenKind::Integer)?;
+            minute =
+                clock_number(self.token().unwrap()).ok_or(error::ErrorKind::InvalidTime { at })?;
+        }
+
+        if self.peek_token().map(|t| t.kind) == Some(TokenKind::Meridiem) {
+            self.advance();
+
+            if !(1..=12).contains(&hour) {
+                return Err(error::ErrorKind::InvalidTime { at });
+            }
+
+            let pm = self.token().unwrap().text.to_lowercase().starts_with('p');
+            hour = hour % 12 + if pm { 12 } else { 0 };
+        }
+
+        if hour > 23 || minute > 59 {
+            return Err(error::ErrorKind::InvalidTime { at });
+        }
+
+        Ok(Expr::Time(TimeLiteral { hour, minute, at }))
+    }
+
+    /// "how many days between June 1 and today", which is how far apart two
+    /// dates (or times) are, in some unit.
+    fn parse_how_many(&mut self) -> Result<Expr> {
+        let at = self.token().unwrap().start;
+
+        let unit = Variable {
+            name: self.expect_peek_name()?,
+            at: self.token_start(),
+        };
+        let unit_span = self.span_from(unit.at);
+
+        self.expect_peek(TokenKind::Between)?;
+        self.advance();
+        let from_start = self.token_start();
+        let from = self.parse_expr(Precedence::Conversion)?;
+        let from_span = self.span_from(from_start);
+
+        self.expect_peek(TokenKind::And)?;
+        self.advance();
+        let to_start = self.token_start();
+        let to = self.parse_expr(Precedence::Conversion)?;
+        let to_span = self.span_from(to_start);
+
+        let difference = Expr::BinExpr(Box::new(BinaryExpr {
+            left: to,
+            op: BinOp::Minus,
+            right: from,
+            at,
+            operands: [to_span, from_span],
+        }));
+
+        Ok(Expr::BinExpr(Box::new(BinaryExpr {
+            operands: [self.span_from(from_start), unit_span],
+            left: Expr::Call(Box::new(Call {
+                name: "abs".to_string(),
+                args: vec![difference],
+                at,
+            })),
+            op: BinOp::In,
+            right: Expr::Unit(unit),
+            at,
+        })))
+    }
+
+    /// Parses "solve 3x + 5 = 20 for x". Which unknown to solve for can go unsaid
+    /// when there's only the one.
+    fn parse_equation(&mut self) -> Result<Expr> {
+        let at = self.token_start();
+        self.advance();
+
+        let (left, right) = self.parse_sides()?;
+
+        let unknown = if self.peek_token().map(|t| t.kind) == Some(TokenKind::For) {
+            self.advance();
+            let name = self.expect_peek_name()?;
+            Some(Variable {
+                name,
+                at: self.token_start(),
+            })
+        } else {
+            None
+        };
+
+        Ok(Expr::Equation(Box::new(Equation {
+            left,
+            right,
+            unknown,
+            at,
+        })))
+    }
+
+    /// Parses "what x makes 3x plus 5 equal 20", which says the unknown up front.
+    fn parse_spoken_equation(&mut self) -> Result<Expr> {
+        let token = self.token().unwrap();
+        let unknown = Variable {
+            name: token.text.to_string(),
+            at: token.start,
+        };
+
+        self.advance();
+        self.advance();
+
+        let (left, right) = self.parse_sides()?;
+
+        Ok(Expr::Equation(Box::new(Equation {
+            left,
+            right,
+            at: unknown.at,
+            unknown: Some(unknown),
+        })))
+    }
+
+    /// The two sides of an equation, either side of `=` or "equals".
+    fn parse_sides(&mut self) -> Result<(Expr, Expr)> {
+        let left = self.parse_expr(Precedence::Equality)?;
+
+        if self.peek_token().map(|t| t.kind) == Some(TokenKind::Equal) {
+            self.advance();
+        } else {
+            self.expect_peek(TokenKind::Assign)?;
+        }
+
+        self.advance();
+        let right = self.parse_expr(Precedence::Equality)?;
+
+        Ok((left, right))
+    }
+
+    /// Parses `d/dx x^3 + 2x`, or "the derivative of x cubed with respect to x",
+    /// where what it's with respect to can go unsaid when there's only the one unknown.
+    fn parse_derivative(&mut self) -> Result<Expr> {
+        let token = self.token().unwrap();
+        let at = token.start;
+        let mut variable = token.text.strip_prefix("d/d").map(|name| Variable {
+            name: name.to_string(),
+            at: at + 3,
+        });
+
+        self.advance();
+        let expr = self.parse_expr(Precedence::Conversion)?;
+
+        if self.peek_token().map(|t| t.kind) == Some(TokenKind::WithRespectTo) {
+            self.advance();
+            let name = self.expect_peek_name()?;
+            variable = Some(Variable {
+                name,
+                at: self.token_start(),
+            });
+        }
+
+        Ok(Expr::Derivative(Box::new(Derivative {
+            expr,
+            variable,
+            at,
+        })))
+    }
+
+    fn parse_variable(&self) -> Expr {
+        let token = self.token().unwrap();
+
+        Expr::Var(Variable {
+            name: token.text.to_string(),
+            at: token.start,
+        })
+    }
+
+    /// Parses `name(a, b)`, or its spoken form `name of a and b`.
+    fn parse_call(&mut self) -> Result<Expr> {
+        let token = self.token().unwrap();
+        let (name, at) = (token.text.to_string(), token.start);
+
+        self.advance();
+
+        let mut args = vec![];
+
+        if self.token().map(|t| t.kind) == Some(TokenKind::Of) {
+            loop {
+                self.advance();
+                args.push(self.parse_expr(Precedence::Sum)?);
+
+                match self.peek_token().map(|t| t.kind) {
+                    Some(TokenKind::Comma | TokenKind::And) => self.advance(),
+                    _ => break,
+                }
+            }
+
+            // "the sum of k squared for k from 1 to 100", or "the integral of x squared
+            // from 0 to 1", which are put in the order of `sum(k, 1, 100, k^2)` and
+            // `integrate(x^2, 0, 1)`
+            if self.peek_token().map(|t| t.kind) == Some(TokenKind::For) {
+                self.advance();
+                let variable = Variable {
+                    name: self.expect_peek_name()?,
+                    at: self.token_start(),
+                };
+                let (from, to) = self.parse_bounds()?;
+                args.splice(0..0, [Expr::Var(variable), from, to]);
+            } else if self.peek_token().map(|t| t.kind) == Some(TokenKind::From) {
+                let (from, to) = self.parse_bounds()?;
+                args.extend([from, to]);
+            }
+        } else if self.peek_token().map(|t| t.kind) == Some(TokenKind::RParen) {
+            self.advance();
+        } else {
+            loop {
+                self.advance();
+                args.push(self.parse_expr(Precedence::None)?);
+
+                if self.peek_token().map(|t| t.kind) == Some(TokenKind::Comma) {
+                    self.advance();
+                } else {
+                    self.expect_peek(TokenKind::RParen)?;
+                    break;
+                }
+            }
+        }
+
+        Ok(into_iterated(Call { name, args, at }))
+    }
+
+    /// Parses "from 1 to 100", where the "to" isn't a conversion.
+    fn parse_bounds(&mut self) -> Result<(Expr, Expr)> {
+        self.expect_peek(TokenKind::From)?;
+        self.advance();
+        let from = self.parse_expr(Precedence::Conversion)?;
+
+        self.expect_peek(TokenKind::In)?;
+        self.advance();
+        let to = self.parse_expr(Precedence::Conversion)?;
+
+        Ok((from, to))
+    }
+
+    /// Looks ahead for `name(a, b) =`, without consuming anything.
+    fn is_function_definition(&self) -> bool {
+        let mut rest = self.tokens[self.position..].iter();
+
+        if !rest.next().is_some_and(is_name)
+            || rest.next().map(|t| t.kind) != Some(TokenKind::LParen)
+        {
+            return false;
+        }
+
+        loop {
+            match rest.next() {
+                Some(t) if is_name(t) => {}
+                Some(t) if t.kind == TokenKind::RParen => break,
+                _ => return false,
+            }
+
+            match rest.next().map(|t| t.kind) {
+                Some(TokenKind::Comma) => continue,
+                Some(TokenKind::RParen) => break,
+                _ => return false,
+            }
+        }
+
+        rest.next().map(|t| t.kind) == Some(TokenKind::Assign)
+    }
+
+    fn parse_function_definition(&mut self) -> Result<Statement> {
+        let name = self.token().unwrap().text.to_string();
+        self.advance();
+
+        let mut params = vec![];
+
+        if self.peek_token().map(|t| t.kind) == Some(TokenKind::RParen) {
+            self.advance();
+        } else {
+            loop {
+                params.push(self.expect_peek_name()?);
+
+                if self.peek_token().map(|t| t.kind) == Some(TokenKind::Comma) {
+                    self.advance();
+                } else {
+                    self.expect_peek(TokenKind::RParen)?;
+                    break;
+                }
+            }
+        }
+
+        self.expect_peek(TokenKind::Assign)?;
+        self.advance();
+
+        Ok(Statement::Function(FunctionDef {
+            name,
+            params,
+            body: self.parse()?,
+        }))
+    }
+
+    /// Parses `define name as value` or `define name of a and b as body`.
+    fn parse_spoken_definition(&mut self) -> Result<Statement> {
+        let name = self.expect_peek_name()?;
+
+        let mut params = vec![];
+
+        if self.peek_token().map(|t| t.kind) == Some(TokenKind::Of) {
+            self.advance();
+
+            loop {
+                params.push(self.expect_peek_name()?);
+
+                match self.peek_token().map(|t| t.kind) {
+                    Some(TokenKind::Comma | TokenKind::And) => self.advance(),
+                    _ => break,
+                }
+            }
+        }
+
+        self.expect_peek(TokenKind::Assign)?;
+        self.advance();
+
+        let body = self.parse()?;
+
+        if params.is_empty() {
+            return Ok(Statement::Assign { name, value: body });
+        }
+
+        Ok(Statement::Function(FunctionDef { name, params, body }))
+    }
+
+    /// Moves onto the next token, only if it can name a variable or function.
+    fn expect_peek_name(&mut self) -> Result<String> {
+        match self.peek_token() {
+            Some(token) if is_name(token) => {
+                let name = token.text.to_string();
+                self.advance();
+                Ok(name)
+            }
+            Some(token) => Err(error::ErrorKind::UnexpectedToken {
+                token: token.into(),
+            }),
+            None => Err(error::ErrorKind::UnexpectedEnd {
+                at: self.tokens.last().map(|t| t.start).unwrap_or_default(),
+            }),
+        }
+    }
+
+    fn parse_if_expr(&mut self) -> Result<Expr> {
+        let at = self.token().map(|t| t.start).unwrap_or_default();
+
+        self.advance();
+        let condition = self.parse_expr(Precedence::None)?;
+
+        self.expect_peek(TokenKind::Then)?;
+        self.advance();
+        let then = self.parse_expr(Precedence::None)?;
+
+        self.expect_peek(TokenKind::Else)?;
+        self.advance();
+        let otherwise = self.parse_expr(Precedence::None)?;
+
+        Ok(Expr::If(Box::new(IfExpr {
+            condition,
+            then,
+            otherwise,
+            at,
+        })))
+    }
+
+    /// Moves onto the next token, only if it is of the `expected` kind.
+    fn expect_peek(&mut self, expected: TokenKind) -> Result<()> {
+        let token = self
+            .tokens
+            .get(self.read_position)
+            .or(self.tokens.last())
+            .expect("the lexer always produces at least an Eof token");
+
+        if token.kind == expected {
+            self.advance();
+            return Ok(());
+        }
+
+        match token.kind {
+            TokenKind::Eof => Err(error::ErrorKind::UnexpectedEnd { at: token.start }),
+            _ => Err(error::ErrorKind::UnexpectedToken {
+                token: token.into(),
+            }),
+        }
+    }
+
+    fn parse_fp_number(&self) -> Expr {
+        let token = self.token().unwrap();
+
+        Expr::Float(
+            token.text.parse().expect(
+                "failed to parse an ostensibly properly tokenized integer (should not happen)",
+            ),
+        )
+    }
+
+    /// Parses dice like "3d6", or "two d20" when how many is said apart from them.
+    fn parse_dice(&mut self) -> Result<Expr> {
+        let at = self.token_start();
+
+        let said = match self.token() {
+            Some(token) if token.kind == TokenKind::Dice => None,
+            Some(token) if token.kind == TokenKind::Integer => {
+                Some(integer_value(token).ok_or(error::ErrorKind::Overflow { at })? as usize)
+            }
+            // "a" or "an"
+            _ => Some(1),
+        };
+        if said.is_some() {
+            self.advance();
+        }
+
+        let token = self.token().unwrap();
+        let (count, sides) = token
+            .text
+            .split_once('d')
+            .expect("dice are tokenized with a d (should not happen)");
+        let number = |digits: &str| {
+            digits
+                .parse()
+                .map_err(|_| error::ErrorKind::Overflow { at })
+        };
+
+        let count = match said {
+            Some(count) => count,
+            None if count.is_empty() => 1,
+            None => number(count)?,
+        };
+
+        Ok(Expr::Dice(Dice {
+            count,
+            sides: number(sides)?,
+            at,
+        }))
+    }
+
+    fn parse_integer(&self) -> Expr {
+        let token = self.token().unwrap();
+
+        Expr::Integer(
+            integer_value(token).expect(
+                "failed to parse an ostensibly properly tokenized floating point number (should not happen)",
+            ),
+        )
+    }
+}
+
+/// Sums, products and integrals, as in `sum(k, 1, 100, k^2)` or `integrate(x^2, 0, 1)`,
+/// whose bodies are worked out for many values of a variable of their own. Anything
+/// else, like the sum of a list, stays a call.
+fn into_iterated(call: Call) -> Expr {
+    let kind = match call.name.as_str() {
+        "sum" | "total" => Iteration::Sum,
+        "product" | "prod" => Iteration::Product,
+        "integrate" | "integral" => Iteration::Integral,
+        _ => return Expr::Call(Box::new(call)),
+    };
+
+    let Call { name, args, at } = call;
+
+    let iterated = |variable, from, to, body| {
+        Expr::Iterated(Box::new(Iterated {
+            kind,
+            variable,
+            from,
+            to,
+            body,
+            at,
+        }))
+    };
+
+    let args = match <[Expr; 4]>::try_from(args) {
+        Ok([body, Expr::Var(variable), from, to]) if matches!(kind, Iteration::Integral) => {
+            return iterated(Some(variable), from, to, body)
+        }
+        Ok([Expr::Var(variable), from, to, body])
+            if body
+                .variables()
+                .into_iter()
+                .chain(body.units())
+                .any(|v| v.name == variable.name) =>
+        {
+            return iterated(Some(variable), from, to, body)
+        }
+        Ok(args) => Vec::from(args),
+        Err(args) => args,
+    };
+
+    match <[Expr; 3]>::try_from(args) {
+        Ok([body, from, to]) if matches!(kind, Iteration::Integral) => {
+            iterated(None, from, to, body)
+        }
+        Ok(args) => Expr::Call(Box::new(Call {
+            name,
+            args: Vec::from(args),
+            at,
+        })),
+        Err(args) => Expr::Call(Box::new(Call { name, args, at })),
+    }
+}
+
+fn is_punctuation(token: &Token) -> bool {
+    token.text.chars().all(|c| c.is_ascii_punctuation())
+}
+
+/// Whether the token could be left over after the problem, as punctuation, or a
+/// word that's not a unit, constant or the like. Single letters are always taken
+/// as unknowns, as in `2 y`.
+fn is_trailing_filler(token: &Token) -> bool {
+    let is_word = token.kind == TokenKind::Ident
+        && token.text.chars().count() > 1
+        && !is_unit(token)
+        && constant(token.text).is_none()
+        && calendar::named(token.text).is_none();
+
+    is_word
+        || token.kind == TokenKind::Comma
+        || (token.kind == TokenKind::Illegal && is_punctuation(token))
+}
+
+/// The value of an integer token, whether it's written in digits or words.
+fn integer_value(token: &Token) -> Option<isize> {
+    token.text.parse().ok().or_else(|| number_word(token.text))
+}
+
+/// A day, hour or minute, whether it's written in digits or words.
+fn clock_number(token: &Token) -> Option<u32> {
+    integer_value(token).and_then(|number| u32::try_from(number).ok())
+}
+
+/// The power said with "squared" or "cubed".
+fn spoken_power(token: &Token) -> Option<isize> {
+    match token.kind {
+        TokenKind::Squared => Some(2),
+        TokenKind::Cubed => Some(3),
+        _ => None,
+    }
+}
+
+/// Whether the token can be the name of a variable or function.
+/// "x" gets lexed as multiplication, but is most likely a name when it's found where an operand should be.
+fn is_name(token: &Token) -> bool {
+    token.kind == TokenKind::Ident || (token.kind == TokenKind::Times && token.text == "x")
+}
+
+fn is_month(token: &Token) -> bool {
+    is_name(token) && calendar::month(token.text).is_some()
+}
+
+/// Whether the token names a unit of measure or a currency.
+fn is_unit(token: &Token) -> bool {
+    is_name(token)
+        && (units::lookup(token.text).is_some() || currency::lookup(token.text).is_some())
+}
+
+/// Whether the token can begin an operand, which tells a filler word (like "what is")
+/// apart from a variable.
+fn starts_operand(token: &Token) -> bool {
+    is_name(token)
+        || matches!(
+            token.kind,
+            TokenKind::Integer
+                | TokenKind::Float
+                | TokenKind::If
+                | TokenKind::Not
+                | TokenKind::LParen
+                | TokenKind::LBracket
+                | TokenKind::Numbers
+                | TokenKind::CurrencySign
+                | TokenKind::Dice
+                | TokenKind::CoinFlip
+        )
+}
+
+#[derive(Debug, Default, PartialEq, PartialOrd)]
+enum Precedence {
+    #[default]
+    None,
+    Conversion,
+    BitOr,
+    Xor,
+    BitAnd,
+    Equality,
+    Comparison,
+    Shift,
+    Sum,
+    Product,
+    Prefix,
+    Power,
+}
+
+impl From<BinOp> for Precedence {
+    fn from(value: BinOp) -> Self {
+        match value {
+            BinOp::Plus => Self::Sum,
+            BinOp::Minus => Self::Sum,
+            BinOp::Times => Self::Product,
+            BinOp::ElementTimes => Self::Product,
+            BinOp::Over => Self::Product,
+            BinOp::BitAnd => Self::BitAnd,
+            BinOp::BitOr => Self::BitOr,
+            BinOp::Xor => Self::Xor,
+            BinOp::ShiftLeft => Self::Shift,
+            BinOp::ShiftRight => Self::Shift,
+            BinOp::Equal => Self::Equality,
+            BinOp::NotEqual => Self::Equality,
+            BinOp::Less => Self::Comparison,
+            BinOp::Greater => Self::Comparison,
+            BinOp::LessEqual => Self::Comparison,
+            BinOp::GreaterEqual => Self::Comparison,
+            BinOp::Power => Self::Power,
+            BinOp::In => Self::Conversion,
+        }
+    }
+}
+
+impl<'t, 's> TryFrom<&'t Token<'s>> for Precedence {
+    type Error = &'t Token<'s>;
+
+    fn try_from(value: &'t Token<'s>) -> std::prelude::v1::Result<Self, Self::Error> {
+        BinOp::try_from(value).map(Precedence::from)
+    }
+}
+
+pub mod ast {
+    use std::fmt::Debug;
+
+    use serde::Serialize;
+
+    use super::lexer::Token;
+
+    #[derive(Serialize)]
+    pub enum Expr {
+        Integer(isize),
+        Float(f64),
+        BinExpr(Box<BinaryExpr>),
+        UnExpr(Box<UnaryExpr>),
+        If(Box<IfExpr>),
+        Var(Variable),
+        Call(Box<Call>),
+        List(Vec<Expr>),
+        /// A unit of measure, attached to a number.
+        Unit(Variable),
+        Date(DateLiteral),
+        Time(TimeLiteral),
+        Equation(Box<Equation>),
+        Derivative(Box<Derivative>),
+        Iterated(Box<Iterated>),
+        Dice(Dice),
+        /// "flip a coin"
+        CoinFlip,
+    }
+
+    impl Expr {
+        /// The variables this expression refers to, in the order they appear.
+        pub fn variables(&self) -> Vec<&Variable> {
+            let mut variables = vec![];
+            self.collect_names(false, &mut variables);
+            variables
+        }
+
+        /// The units written against numbers in this expression, as the `t` of `3t`,
+        /// which could instead be unknowns.
+        pub fn units(&self) -> Vec<&Variable> {
+            let mut units = vec![];
+            self.collect_names(true, &mut units);
+            units
+        }
+
+        fn collect_names<'e>(&'e self, units: bool, names: &mut Vec<&'e Variable>) {
+            match self {
+                Expr::Var(var) if !units => names.push(var),
+                Expr::Unit(unit) if units => names.push(unit),
+                Expr::BinExpr(expr) => {
+                    expr.left.collect_names(units, names);
+                    expr.right.collect_names(units, names);
+                }
+                Expr::UnExpr(expr) => expr.right.collect_names(units, names),
+                Expr::If(expr) => {
+                    expr.condition.collect_names(units, names);
+                    expr.then.collect_names(units, names);
+                    expr.otherwise.collect_names(units, names);
+                }
+                Expr::Call(call) => call
+                    .args
+                    .iter()
+                    .for_each(|arg| arg.collect_names(units, names)),
+                Expr::List(items) => items
+                    .iter()
+                    .for_each(|item| item.collect_names(units, names)),
+                Expr::Equation(equation) => {
+                    equation.left.collect_names(units, names);
+                    equation.right.collect_names(units, names);
+                }
+                Expr::Derivative(derivative) => derivative.expr.collect_names(units, names),
+                Expr::Iterated(iterated) => {
+                    iterated.from.collect_names(units, names);
+                    iterated.to.collect_names(units, names);
+                    iterated.body.collect_names(units, names);
+                }
+                Expr::Integer(_)
+                | Expr::Float(_)
+                | Expr::Var(_)
+                | Expr::Unit(_)
+                | Expr::Date(_)
+                | Expr::Time(_)
+                | Expr::Dice(_)
+                | Expr::CoinFlip => {}
+            }
+        }
+    }
+
+    impl Debug for Expr {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match self {
+                Expr::Integer(number) => write!(f, "{number}"),
+                Expr::Float(number) => write!(f, "{number}"),
+                Expr::BinExpr(expr) => write!(f, "{expr:?}"),
+                Expr::UnExpr(expr) => write!(f, "{expr:?}"),
+                Expr::If(expr) => write!(f, "{expr:?}"),
+                Expr::Var(var) => write!(f, "{}", var.name),
+                Expr::Call(call) => write!(f, "{call:?}"),
+                Expr::List(items) => f.debug_list().entries(items).finish(),
+                Expr::Unit(unit) => write!(f, "{}", unit.name),
+                Expr::Date(date) => write!(f, "{date:?}"),
+                Expr::Time(time) => write!(f, "{time:?}"),
+                Expr::Equation(equation) => write!(f, "{equation:?}"),
+                Expr::Derivative(derivative) => write!(f, "{derivative:?}"),
+                Expr::Iterated(iterated) => write!(f, "{iterated:?}"),
+                Expr::Dice(dice) => write!(f, "{}d{}", dice.count, dice.sides),
+                Expr::CoinFlip => write!(f, "coin flip"),
+            }
+        }
+    }
+
+    /// A roll of `count` dice with `sides` sides each, like the "3d6" of tabletop games.
+    #[derive(Serialize)]
+    pub struct Dice {
+        pub count: usize,
+        pub sides: usize,
+        pub at: usize,
+    }
+
+    /// A date as it was said, which is checked to exist once the year is known.
+    #[derive(Serialize)]
+    pub struct DateLiteral {
+        /// This year, if it's left out.
+        pub year: Option<i32>,
+        pub month: u32,
+        pub day: u32,
+        pub at: usize,
+    }
+
+    impl Debug for DateLiteral {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match self.year {
+                Some(year) => write!(f, "{year}-{:02}-{:02}", self.month, self.day),
+                None => write!(f, "{:02}-{:02}", self.month, self.day),
+            }
+        }
+    }
+
+    /// A time of day, on the 24 hour clock.
+    #[derive(Serialize)]
+    pub struct TimeLiteral {
+        pub hour: u32,
+        pub minute: u32,
+        pub at: usize,
+    }
+
+    impl Debug for TimeLiteral {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{:02}:{:02}", self.hour, self.minute)
+        }
+    }
+
+    #[derive(Serialize)]
+    pub struct BinaryExpr {
+        pub left: Expr,
+        pub op: BinOp,
+        pub right: Expr,
+        /// Where the operator is in the source.
+        pub at: usize,
+        /// Where the left and right operands are in the source.
+        pub operands: [Span; 2],
+    }
+
+    /// A stretch of the source, from `start` up to but not including `end`.
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+    pub struct Span {
+        pub start: usize,
+        pub end: usize,
+    }
+
+    impl Debug for BinaryExpr {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "({:?} {:?} {:?})", self.left, self.op, self.right)
+        }
+    }
+
+    #[derive(Serialize)]
+    pub struct UnaryExpr {
+        pub op: UnOp,
+        pub right: Expr,
+        /// Where the operator is in the source.
+        pub at: usize,
+    }
+
+    impl Debug for UnaryExpr {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "({:?}{:?})", self.op, self.right)
+        }
+    }
+
+    /// A conditional, of which only the branch picked by the condition gets evaluated.
+    #[derive(Serialize)]
+    pub struct IfExpr {
+        pub condition: Expr,
+        pub then: Expr,
+        pub otherwise: Expr,
+        /// Where the `if` is in the source.
+        pub at: usize,
+    }
+
+    impl Debug for IfExpr {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(
+                f,
+                "(if {:?} then {:?} else {:?})",
+                self.condition, self.then, self.otherwise
+            )
+        }
+    }
+
+    /// An equation in one unknown, which evaluates to the values that solve it.
+    #[derive(Serialize)]
+    pub struct Equation {
+        pub left: Expr,
+        pub right: Expr,
+        /// What to solve for, if it's been said.
+        pub unknown: Option<Variable>,
+        /// Where the equation starts in the source.
+        pub at: usize,
+    }
+
+    impl Debug for Equation {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "(solve {:?} = {:?}", self.left, self.right)?;
+            if let Some(unknown) = &self.unknown {
+                write!(f, " for {}", unknown.name)?;
+            }
+            write!(f, ")")
+        }
+    }
+
+    /// The derivative of an expression, with respect to one of its unknowns.
+    #[derive(Serialize)]
+    pub struct Derivative {
+        pub expr: Expr,
+        /// What it's with respect to, if it's been said.
+        pub variable: Option<Variable>,
+        /// Where the derivative starts in the source.
+        pub at: usize,
+    }
+
+    impl Debug for Derivative {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match &self.variable {
+                Some(variable) => write!(f, "(d/d{} {:?})", variable.name, self.expr),
+                None => write!(f, "(d/d? {:?})", self.expr),
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, Serialize)]
+    pub enum Iteration {
+        Sum,
+        Product,
+        Integral,
+    }
+
+    /// A sum or product of the body over a range of whole numbers, or its integral
+    /// over an interval, with `variable` standing for each number in turn.
+    #[derive(Serialize)]
+    pub struct Iterated {
+        pub kind: Iteration,
+        /// What the body is in terms of, if it's been said.
+        pub variable: Option<Variable>,
+        pub from: Expr,
+        pub to: Expr,
+        pub body: Expr,
+        /// Where the name of the sum, product or integral is in the source.
+        pub at: usize,
+    }
+
+    impl Debug for Iterated {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            let variable = self.variable.as_ref().map_or("?", |v| v.name.as_str());
+            write!(
+                f,
+                "({:?} {} from {:?} to {:?} of {:?})",
+                self.kind, variable, self.from, self.to, self.body
+            )
+        }
+    }
+
+    #[derive(Serialize)]
+    pub struct Variable {
+        pub name: String,
+        /// Where the name is in the source.
+        pub at: usize,
+    }
+
+    #[derive(Serialize)]
+    pub struct Call {
+        pub name: String,
+        pub args: Vec<Expr>,
+        /// Where the function's name is in the source.
+        pub at: usize,
+    }
+
+    impl Debug for Call {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{}(", self.name)?;
+            for (i, arg) in self.args.iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+                write!(f, "{arg:?}")?;
+            }
+            write!(f, ")")
+        }
+    }
+
+    #[derive(Serialize)]
+    pub enum Statement {
+        Expr(Expr),
+        Assign { name: String, value: Expr },
+        Function(FunctionDef),
+    }
+
+    impl Debug for Statement {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match self {
+                Statement::Expr(expr) => write!(f, "{expr:?}"),
+                Statement::Assign { name, value } => write!(f, "{name} = {value:?}"),
+                Statement::Function(function) => write!(f, "{function:?}"),
+            }
+        }
+    }
+
+    #[derive(Serialize)]
+    pub struct FunctionDef {
+        pub name: String,
+        pub params: Vec<String>,
+        pub body: Expr,
+    }
+
+    impl Debug for FunctionDef {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(
+                f,
+                "{}({}) = {:?}",
+                self.name,
+                self.params.join(", "),
+                self.body
+            )
+        }
+    }
+
+    #[derive(Clone, Copy, Serialize)]
+    pub enum BinOp {
+        Plus,
+        Minus,
+        Times,
+        /// Multiplying matrices entry by entry, instead of as matrices.
+        ElementTimes,
+        Over,
+        BitAnd,
+        BitOr,
+        Xor,
+        ShiftLeft,
+        ShiftRight,
+        Less,
+        Greater,
+        LessEqual,
+        GreaterEqual,
+        Equal,
+        NotEqual,
+        Power,
+        In,
+    }
+
+    impl Debug for BinOp {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match self {
+                BinOp::Plus => write!(f, "+"),
+                BinOp::Minus => write!(f, "-"),
+                BinOp::Times => write!(f, "*"),
+                BinOp::ElementTimes => write!(f, ".*"),
+                BinOp::Over => write!(f, "/"),
+                BinOp::BitAnd => write!(f, "&"),
+                BinOp::BitOr => write!(f, "|"),
+                BinOp::Xor => write!(f, "xor"),
+                BinOp::ShiftLeft => write!(f, "<<"),
+                BinOp::ShiftRight => write!(f, ">>"),
+                BinOp::Less => write!(f, "<"),
+                BinOp::Greater => write!(f, ">"),
+                BinOp::LessEqual => write!(f, "<="),
+                BinOp::GreaterEqual => write!(f, ">="),
+                BinOp::Equal => write!(f, "=="),
+                BinOp::NotEqual => write!(f, "!="),
+                BinOp::Power => write!(f, "^"),
+                BinOp::In => write!(f, "in"),
+            }
+        }
+    }
+
+    impl<'t, 's> TryFrom<&'t Token<'s>> for BinOp {
+        type Error = &'t Token<'s>;
+
+        fn try_from(value: &'t Token<'s>) -> Result<Self, Self::Error> {
+            let r = match value.kind {
+                super::lexer::TokenKind::Minus => BinOp::Minus,
+                super::lexer::TokenKind::Times => BinOp::Times,
+                super::lexer::TokenKind::ElementTimes => BinOp::ElementTimes,
+                super::lexer::TokenKind::Over => BinOp::Over,
+                super::lexer::TokenKind::Plus => BinOp::Plus,
+                super::lexer::TokenKind::BitAnd => BinOp::BitAnd,
+                super::lexer::TokenKind::BitOr => BinOp::BitOr,
+                super::lexer::TokenKind::Xor => BinOp::Xor,
+                super::lexer::TokenKind::ShiftLeft => BinOp::ShiftLeft,
+                super::lexer::TokenKind::ShiftRight => BinOp::ShiftRight,
+                super::lexer::TokenKind::Less => BinOp::Less,
+                super::lexer::TokenKind::Greater => BinOp::Greater,
+                super::lexer::TokenKind::LessEqual => BinOp::LessEqual,
+                super::lexer::TokenKind::GreaterEqual => BinOp::GreaterEqual,
+                super::lexer::TokenKind::Equal => BinOp::Equal,
+                super::lexer::TokenKind::NotEqual => BinOp::NotEqual,
+                super::lexer::TokenKind::Power => BinOp::Power,
+                super::lexer::TokenKind::In => BinOp::In,
+                super::lexer::TokenKind::From => BinOp::Plus,
+                _ => return Err(value),
+            };
+
+            Ok(r)
+        }
+    }
+
+    #[derive(Serialize)]
+    pub enum UnOp {
+        Plus,
+        Minus,
+        Not,
+    }
+
+    impl Debug for UnOp {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match self {
+                UnOp::Plus => write!(f, "+"),
+                UnOp::Minus => write!(f, "-"),
+                UnOp::Not => write!(f, "~"),
+            }
+        }
+    }
+
+    impl<'t, 's> TryFrom<&'t Token<'s>> for UnOp {
+        type Error = &'t Token<'s>;
+
+        fn try_from(value: &'t Token<'s>) -> Result<Self, Self::Error> {
+            let r = match value.kind {
+                super::lexer::TokenKind::Minus => UnOp::Minus,
+                super::lexer::TokenKind::Plus => UnOp::Plus,
+                super::lexer::TokenKind::Not => UnOp::Not,
+                _ => return Err(value),
+            };
+
+            Ok(r)
+        }
+    }
+}
+
+pub mod error {
+    use std::fmt::Display;
+
+    use serde::Serialize;
+
+    use super::{
+        ast::Span,
+        lexer::{Token, TokenKind},
+    };
+
+    #[derive(Debug, Serialize)]
+    pub struct TokenKindAt {
+        pub position: usize,
+        /// Where the token ends in the source.
+        pub end: usize,
+        pub kind: TokenKind,
+    }
+
+    impl From<&Token<'_>> for TokenKindAt {
+        fn from(value: &Token<'_>) -> Self {
+            Self {
+                position: value.start,
+                end: value.start + value.text.len(),
+                kind: value.kind,
+            }
+        }
+    }
+
+    /// Something wrong with a problem, and the position in its source it's at,
+    /// which [`crate::render_error`] shows.
+    #[derive(Debug, Serialize)]
+    #[serde(tag = "kind")]
+    pub enum ErrorKind {
+        UnexpectedToken {
+            token: TokenKindAt,
+        },
+        UnexpectedEnd {
+            at: usize,
+        },
+        TypeMismatch {
+            expected: &'static str,
+            found: &'static str,
+            at: usize,
+        },
+        Overflow {
+            at: usize,
+        },
+        EmptyList {
+            at: usize,
+        },
+        IncompatibleUnits {
+            left: String,
+            right: String,
+            at: usize,
+        },
+        MissingRate {
+            from: &'static str,
+            to: &'static str,
+            at: usize,
+        },
+        InvalidDate {
+            at: usize,
+        },
+        ShapeMismatch {
+            left: (usize, usize),
+            right: (usize, usize),
+            /// Where the mismatched operands are, when they're either side of an operator.
+            operands: Option<[Span; 2]>,
+            at: usize,
+        },
+        NotSquare {
+            shape: (usize, usize),
+            at: usize,
+        },
+        Singular {
+            at: usize,
+        },
+        InvalidTime {
+            at: usize,
+        },
+        /// There's no telling what an equation is meant to be solved for, or a
+        /// derivative taken with respect to.
+        UnclearUnknown {
+            candidates: Vec<String>,
+            at: usize,
+        },
+        NotPolynomial {
+            unknown: String,
+            at: usize,
+        },
+        NoSolution {
+            unknown: String,
+            at: usize,
+        },
+        EverySolution {
+            unknown: String,
+            at: usize,
+        },
+        NotDifferentiable {
+            at: usize,
+        },
+        IterationLimit {
+            limit: usize,
+            at: usize,
+        },
+        EmptyRange {
+            from: isize,
+            to: isize,
+            at: usize,
+        },
+        AmbiguousJuxtaposition {
+            at: usize,
+        },
+        UnknownVariable {
+            name: String,
+            at: usize,
+        },
+        UnknownFunction {
+            name: String,
+            at: usize,
+        },
+        WrongArgumentCount {
+            name: String,
+            expected: usize,
+            found: usize,
+            at: usize,
+        },
+        RecursionLimit {
+            name: String,
+            at: usize,
+        },
+        /// An `import` in a script that isn't followed by a quoted file name.
+        InvalidImport {
+            at: usize,
+        },
+        /// A currency sign, like `₩`, for a currency that caldi doesn't know.
+        UnknownCurrency {
+            sign: String,
+            at: usize,
+        },
+        /// An imported script that couldn't be found or read.
+        ImportFailed {
+            path: String,
+            reason: String,
+            at: usize,
+        },
+    }
+
+    impl ErrorKind {
+        /// Where in the source the error is pointed out.
+        pub fn position(&self) -> usize {
+            match self {
+                ErrorKind::UnexpectedToken { token } => token.position,
+                ErrorKind::UnexpectedEnd { at } => *at,
+                ErrorKind::TypeMismatch { at, .. } => *at,
+                ErrorKind::Overflow { at } => *at,
+                ErrorKind::EmptyList { at } => *at,
+                ErrorKind::IncompatibleUnits { at, .. } => *at,
+                ErrorKind::MissingRate { at, .. } => *at,
+                ErrorKind::InvalidDate { at } => *at,
+                ErrorKind::InvalidTime { at } => *at,
+                ErrorKind::ShapeMismatch { at, .. } => *at,
+                ErrorKind::NotSquare { at, .. } => *at,
+                ErrorKind::Singular { at } => *at,
+                ErrorKind::UnclearUnknown { at, .. } => *at,
+                ErrorKind::NotPolynomial { at, .. } => *at,
+                ErrorKind::NoSolution { at, .. } => *at,
+                ErrorKind::EverySolution { at, .. } => *at,
+                ErrorKind::NotDifferentiable { at } => *at,
+                ErrorKind::IterationLimit { at, .. } => *at,
+                ErrorKind::EmptyRange { at, .. } => *at,
+                ErrorKind::AmbiguousJuxtaposition { at } => *at,
+                ErrorKind::UnknownVariable { at, .. } => *at,
+                ErrorKind::UnknownFunction { at, .. } => *at,
+                ErrorKind::WrongArgumentCount { at, .. } => *at,
+                ErrorKind::RecursionLimit { at, .. } => *at,
+                ErrorKind::InvalidImport { at } => *at,
+                ErrorKind::UnknownCurrency { at, .. } => *at,
+                ErrorKind::ImportFailed { at, .. } => *at,
+            }
+        }
+
+        /// The part of the source that's at fault, which is just the [position]
+        /// when there's nothing more to go on.
+        ///
+        /// [position]: ErrorKind::position
+        pub fn span(&self) -> Span {
+            match self {
+                ErrorKind::UnexpectedToken { token } => Span {
+                    start: token.position,
+                    end: token.end,
+                },
+                ErrorKind::ShapeMismatch {
+                    operands: Some([left, right]),
+                    ..
+                } => Span {
+                    start: left.start,
+                    end: right.end,
+                },
+                _ => Span {
+                    start: self.position(),
+                    end: self.position(),
+                },
+            }
+        }
+    }
+
+    impl std::error::Error for ErrorKind {}
+
+    impl Display for ErrorKind {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match self {
+                ErrorKind::UnexpectedToken { token } => write!(
+                    f,
+                    "unexpected token {:?} at position {}",
+                    token.kind, token.position
+                ),
+                ErrorKind::UnexpectedEnd { at } => write!(
+                    f,
+                    "unexpected end of expression encountered at position {}",
+                    at
+                ),
+                ErrorKind::TypeMismatch {
+                    expected,
+                    found,
+                    at,
+                } => write!(
+                    f,
+                    "expected {} but found {} at position {}",
+                    expected, found, at
+                ),
+                ErrorKind::Overflow { at } => {
+                    write!(f, "arithmetic overflow at position {}", at)
+                }
+                ErrorKind::EmptyList { at } => {
+                    write!(f, "expected at least one number at position {}", at)
+                }
+                ErrorKind::IncompatibleUnits { left, right, at } => write!(
+                    f,
+                    "incompatible units, {} and {}, at position {}",
+                    left, right, at
+                ),
+                ErrorKind::MissingRate { from, to, at } => write!(
+                    f,
+                    "no exchange rate from {} to {} at position {}",
+                    from, to, at
+                ),
+                ErrorKind::InvalidDate { at } => write!(f, "no such date at position {}", at),
+                ErrorKind::ShapeMismatch {
+                    left, right, at, ..
+                } => write!(
+                    f,
+                    "mismatched shapes, {}x{} and {}x{}, at position {}",
+                    left.0, left.1, right.0, right.1, at
+                ),
+                ErrorKind::NotSquare { shape, at } => write!(
+                    f,
+                    "expected a square matrix but found a {}x{} one at position {}",
+                    shape.0, shape.1, at
+                ),
+                ErrorKind::Singular { at } => {
+                    write!(f, "the matrix has no inverse at position {}", at)
+                }
+                ErrorKind::InvalidTime { at } => {
+                    write!(f, "no such time of day at position {}", at)
+                }
+                ErrorKind::UnclearUnknown { candidates, at } if candidates.is_empty() => write!(
+                    f,
+                    "expected an unknown but found none at position {}",
+                    at
+                ),
+                ErrorKind::UnclearUnknown { candidates, at } => write!(
+                    f,
+                    "expected a single unknown but found {} at position {}",
+                    candidates.join(" and "),
+                    at
+                ),
+                ErrorKind::NotPolynomial { unknown, at } => write!(
+                    f,
+                    "can only solve for {} when the equation is a polynomial in it, at position {}",
+                    unknown, at
+                ),
+                ErrorKind::NoSolution { unknown, at } => {
+                    write!(
+                        f,
+                        "no value of {} solves the equation at position {}",
+                        unknown, at
+                    )
+                }
+                ErrorKind::EverySolution { unknown, at } => write!(
+                    f,
+                    "every value of {} solves the equation at position {}",
+                    unknown, at
+                ),
+                ErrorKind::AmbiguousJuxtaposition { at } => write!(
+                    f,
+                    "expected an operator between the operand and the number at position {}",
+                    at
+                ),
+                ErrorKind::EmptyRange { from, to, at } => write!(
+                    f,
+                    "there are no whole numbers from {} to {} at position {}",
+                    from, to, at
+                ),
+                ErrorKind::IterationLimit { limit, at } => write!(
+                    f,
+                    "gave up after {} iterations at position {}",
+                    limit, at
+                ),
+                ErrorKind::NotDifferentiable { at } => write!(
+                    f,
+                    "can't differentiate a power with the unknown in both its base and exponent at position {}",
+                    at
+                ),
+                ErrorKind::UnknownVariable { name, at } => {
+                    write!(f, "unknown variable {} at position {}", name, at)
+                }
+                ErrorKind::UnknownFunction { name, at } => {
+                    write!(f, "unknown function {} at position {}", name, at)
+                }
+                ErrorKind::WrongArgumentCount {
+                    name,
+                    expected,
+                    found,
+                    at,
+                } => write!(
+                    f,
+                    "{} takes {} argument(s) but was given {} at position {}",
+                    name, expected, found, at
+                ),
+                ErrorKind::RecursionLimit { name, at } => {
+                    write!(f, "too many nested calls to {} at position {}", name, at)
+                }
+                ErrorKind::InvalidImport { at } => write!(
+                    f,
+                    "expected the name of a file in quotes after import at position {}",
+                    at
+                ),
+                ErrorKind::UnknownCurrency { sign, at } => {
+                    write!(f, "unknown currency {} at position {}", sign, at)
+                }
+                ErrorKind::ImportFailed { path, reason, at } => write!(
+                    f,
+                    "failed to import {} ({}) at position {}",
+                    path, reason, at
+                ),
+            }
+        }
+    }
+}
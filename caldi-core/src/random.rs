@@ -0,0 +1,52 @@
+use std::fmt::Display;
+
+use fastrand::Rng;
+use serde::{Deserialize, Serialize};
+
+use super::value::{OperandError, Result};
+
+/// Which way up a flipped coin lands.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Coin {
+    Heads,
+    Tails,
+}
+
+impl Coin {
+    pub fn flip(rng: &mut Rng) -> Self {
+        if rng.bool() {
+            Coin::Heads
+        } else {
+            Coin::Tails
+        }
+    }
+}
+
+impl Display for Coin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Coin::Heads => write!(f, "heads"),
+            Coin::Tails => write!(f, "tails"),
+        }
+    }
+}
+
+/// A whole number from `from` to `to`, both included.
+pub fn integer(rng: &mut Rng, from: isize, to: isize) -> Result<isize> {
+    if from > to {
+        return Err(OperandError::EmptyRange { from, to });
+    }
+
+    Ok(rng.isize(from..=to))
+}
+
+/// The total of `count` dice with `sides` sides each.
+pub fn roll(rng: &mut Rng, count: usize, sides: usize) -> Result<isize> {
+    let sides = isize::try_from(sides).map_err(|_| OperandError::Overflow)?;
+
+    (0..count).try_fold(0isize, |total, _| {
+        total
+            .checked_add(integer(rng, 1, sides)?)
+            .ok_or(OperandError::Overflow)
+    })
+}
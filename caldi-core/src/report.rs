@@ -0,0 +1,77 @@
+use serde_json::{json, Value as Json};
+
+use super::{
+    parse::{error::ErrorKind, Parser},
+    session::{Outcome, Session},
+    value::Value,
+};
+
+/// Answers `source` as [`Session::eval`] does, but as JSON for other programs to
+/// read: the source, how it was understood, its AST, and either the answer with its
+/// type or the error with the span of the source at fault.
+pub fn report(session: &mut Session, source: &str) -> Json {
+    let statement = match Parser::new(source).parse_statement() {
+        Ok(statement) => statement,
+        Err(error) => {
+            session.remember(source, Err(error.to_string()));
+            return report_error(source, &error);
+        }
+    };
+
+    let mut report = json!({
+        "source": source,
+        "normalized": format!("{statement:?}"),
+        "ast": statement,
+    });
+
+    match session.run(statement, source) {
+        Ok(outcome) => {
+            let text = session.answer(&outcome);
+            session.remember(source, Ok(text.clone()));
+            report["result"] = result(&outcome, text);
+        }
+        Err(error) => {
+            session.remember(source, Err(error.to_string()));
+            report["error"] = error_report(&error);
+        }
+    }
+
+    report
+}
+
+/// Reports an error with `source` that came up before it could be evaluated.
+pub fn report_error(source: &str, error: &ErrorKind) -> Json {
+    json!({ "source": source, "error": error_report(error) })
+}
+
+fn result(outcome: &Outcome, text: String) -> Json {
+    let (kind, value) = match outcome {
+        Outcome::Value(value) => (value.type_name(), plain(value)),
+        Outcome::Defined(name) => ("function", json!(name)),
+    };
+
+    json!({
+        "type": kind,
+        "value": value,
+        "text": text,
+    })
+}
+
+/// Numbers, booleans and lists of them as they are in JSON, and anything else as
+/// it reads.
+fn plain(value: &Value) -> Json {
+    match value {
+        Value::Integer(i) => json!(i),
+        Value::Float(f) => json!(f),
+        Value::Bool(b) => json!(b),
+        Value::List(items) => items.iter().map(plain).collect(),
+        other => json!(other.to_string()),
+    }
+}
+
+fn error_report(error: &ErrorKind) -> Json {
+    let mut report = json!(error);
+    report["message"] = json!(error.to_string());
+    report["span"] = json!(error.span());
+    report
+}
@@ -0,0 +1,127 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+
+use super::parse::{self, error::ErrorKind};
+
+/// A `.caldi` script, of statements one per line or separated by `;`, with `#`
+/// starting a comment that runs to the end of the line.
+///
+/// A statement can also be `import "other.caldi"`, which runs another script,
+/// found relative to this one, for its variables and functions.
+pub struct Script {
+    path: PathBuf,
+    source: String,
+}
+
+/// A statement of a script, and where it is.
+pub struct Statement<'s> {
+    pub file: &'s Path,
+    pub source: &'s str,
+    /// The line the statement is on, counting from 1 as editors do.
+    pub line: usize,
+    /// The column the statement starts at, also counting from 1.
+    pub column: usize,
+}
+
+impl Script {
+    pub fn new(path: PathBuf, source: String) -> Self {
+        Self { path, source }
+    }
+
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let source = fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+
+        Ok(Self::new(path.to_path_buf(), source))
+    }
+
+    /// The statements in the order they're to be run, leaving out comments and
+    /// blank lines.
+    pub fn statements(&self) -> impl Iterator<Item = Statement<'_>> {
+        self.source.lines().enumerate().flat_map(move |(i, line)| {
+            split_line(line)
+                .into_iter()
+                .map(move |(start, source)| Statement {
+                    file: &self.path,
+                    source,
+                    line: i + 1,
+                    column: line[..start].chars().count() + 1,
+                })
+        })
+    }
+
+    /// Where the script imported as `name` is, which is relative to this one.
+    pub fn resolve(&self, name: &str) -> PathBuf {
+        match self.path.parent() {
+            Some(dir) => dir.join(name),
+            None => PathBuf::from(name),
+        }
+    }
+}
+
+impl Statement<'_> {
+    /// The name of the script it imports, if it's an import.
+    pub fn import(&self) -> Option<parse::Result<&str>> {
+        let rest = self.source.strip_prefix("import")?;
+        if !rest.starts_with(|c: char| c.is_whitespace() || c == '"') {
+            // just a name that starts with "import"
+            return None;
+        }
+
+        let rest = rest.trim_start();
+        let name = rest
+            .strip_prefix('"')
+            .and_then(|name| name.strip_suffix('"'))
+            .filter(|name| !name.is_empty() && !name.contains('"'));
+
+        Some(name.ok_or(ErrorKind::InvalidImport {
+            at: self.source.len() - rest.len(),
+        }))
+    }
+
+    /// The column that `position` in the statement is at.
+    pub fn column_of(&self, position: usize) -> usize {
+        let before = self.source.get(..position).unwrap_or(self.source);
+        self.column + before.chars().count()
+    }
+}
+
+/// The statements on a line, without its comment, each with where it starts.
+fn split_line(line: &str) -> Vec<(usize, &str)> {
+    let mut pieces = vec![];
+    let mut start = 0;
+    let mut end = line.len();
+    // a `;` or `#` in the name of an import is part of it
+    let mut quoted = false;
+
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            ';' if !quoted => {
+                pieces.push((start, &line[start..i]));
+                start = i + 1;
+            }
+            '#' if !quoted => {
+                end = i;
+                break;
+            }
+            _ => {}
+        }
+    }
+    pieces.push((start, &line[start..end]));
+
+    pieces
+        .into_iter()
+        .filter_map(|(start, piece)| {
+            let trimmed = piece.trim_start();
+            let start = start + piece.len() - trimmed.len();
+            let trimmed = trimmed.trim_end();
+
+            (!trimmed.is_empty()).then_some((start, trimmed))
+        })
+        .collect()
+}
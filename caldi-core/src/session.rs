@@ -0,0 +1,385 @@
+use std::{
+    cell::{RefCell, RefMut},
+    collections::HashMap,
+    fs, mem,
+    path::Path,
+};
+
+use anyhow::Context;
+use fastrand::Rng;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    builtins,
+    currency::{self, Rates},
+    eval_expr,
+    format::{self, Format},
+    parse::{
+        self,
+        ast::{FunctionDef, Statement},
+        Parser,
+    },
+    units,
+    value::Value,
+    CONSTANTS,
+};
+
+/// How deep user defined functions may call into each other (or themselves)
+/// before we give up, instead of overflowing the stack.
+pub const MAX_CALL_DEPTH: usize = 64;
+
+/// How many of the latest inputs a session remembers, with their answers.
+const MAX_HISTORY: usize = 1000;
+
+/// Variables and functions defined so far, for later inputs to refer to.
+///
+/// It's saved as JSON, without the exchange rates, which are read afresh, or the
+/// functions, which are parsed again from their definitions.
+#[derive(Default, Serialize, Deserialize)]
+pub struct Session {
+    variables: HashMap<String, Value>,
+    #[serde(skip)]
+    functions: HashMap<String, FunctionDef>,
+    #[serde(skip)]
+    rates: Rates,
+    /// Where random numbers and dice rolls come from, which is behind a `RefCell`
+    /// since expressions only get to see the session through a shared reference.
+    #[serde(skip)]
+    rng: RefCell<Rng>,
+    /// The inputs that defined a variable or function, in the order they were made.
+    definitions: Vec<Definition>,
+    history: Vec<Exchange>,
+    pub settings: Settings,
+}
+
+/// What evaluating a statement came to.
+pub(super) enum Outcome {
+    Value(Value),
+    /// The name of the function that was defined.
+    Defined(String),
+}
+
+#[derive(Serialize, Deserialize)]
+struct Definition {
+    name: String,
+    source: String,
+}
+
+/// An input, and its answer or what went wrong with it.
+#[derive(Serialize, Deserialize)]
+pub struct Exchange {
+    pub input: String,
+    pub answer: Result<String, String>,
+}
+
+/// Choices about how answers are worked out.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    /// Whether to answer with complex numbers where there'd otherwise be no answer,
+    /// like the square root of a negative number.
+    pub complex: bool,
+    /// How many terms a sum or product may have, and how many times the function
+    /// being integrated may be worked out.
+    pub max_iterations: usize,
+    pub format: Format,
+    /// How many decimal places to round answers to, if they're to be rounded.
+    pub precision: Option<usize>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            complex: false,
+            max_iterations: 100_000,
+            format: Format::default(),
+            precision: None,
+        }
+    }
+}
+
+impl Session {
+    /// Starts a session that converts currencies with `rates`.
+    pub fn with_rates(rates: Rates) -> Self {
+        Self {
+            rates,
+            ..Default::default()
+        }
+    }
+
+    /// Makes the random numbers from here on the same every time for the same `seed`.
+    pub fn seed(&mut self, seed: u64) {
+        self.rng.get_mut().seed(seed);
+    }
+
+    /// Answers `source`, which can define a variable or function for later, as it
+    /// would be written out by caldi, and remembers it in the history.
+    pub fn eval(&mut self, source: &str) -> parse::Result<String> {
+        let mut parser = Parser::new(source);
+
+        let answer = parser.parse_statement().and_then(|statement| {
+            log::debug!(target: "calc", "ast: {statement:?}");
+
+            let outcome = self.run(statement, source)?;
+            Ok(self.answer(&outcome))
+        });
+
+        let remembered = answer.as_ref().map(Clone::clone);
+        self.remember(source, remembered.map_err(ToString::to_string));
+        answer
+    }
+
+    /// Adds an input to the history, forgetting the oldest once there's too many.
+    pub(super) fn remember(&mut self, input: &str, answer: Result<String, String>) {
+        if self.history.len() == MAX_HISTORY {
+            self.history.remove(0);
+        }
+
+        self.history.push(Exchange {
+            input: input.trim().to_string(),
+            answer,
+        });
+    }
+
+    /// Evaluates a statement parsed from `source`, defining whatever it defines.
+    pub(super) fn run(&mut self, statement: Statement, source: &str) -> parse::Result<Outcome> {
+        match statement {
+            Statement::Expr(expr) => {
+                let value = eval_expr(&expr, &Scope::global(self))?;
+                Ok(Outcome::Value(value))
+            }
+            Statement::Assign { name, value } => {
+                let value = eval_expr(&value, &Scope::global(self))?;
+
+                self.define(&name, source);
+                self.variables.insert(name, value.clone());
+
+                Ok(Outcome::Value(value))
+            }
+            Statement::Function(function) => {
+                let name = function.name.clone();
+
+                self.define(&name, source);
+                self.functions.insert(name.clone(), function);
+
+                Ok(Outcome::Defined(name))
+            }
+        }
+    }
+
+    /// Writes out what a statement came to, as it's answered.
+    pub(super) fn answer(&self, outcome: &Outcome) -> String {
+        match outcome {
+            Outcome::Value(value) => format::answer(value, &self.settings),
+            Outcome::Defined(name) => format!("defined {name}"),
+        }
+    }
+
+    fn define(&mut self, name: &str, source: &str) {
+        self.definitions.push(Definition {
+            name: name.to_string(),
+            source: source.trim().to_string(),
+        });
+    }
+
+    /// Writes the session out to `path`, for [`restore`] to bring back later.
+    ///
+    /// [`restore`]: Session::restore
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let contents = serde_json::to_string_pretty(self)?;
+        fs::write(path, contents).with_context(|| format!("failed to save to {}", path.display()))
+    }
+
+    /// Brings back the variables, functions, history and settings of the session
+    /// saved to `path`, in place of this one's.
+    pub fn restore(&mut self, path: &Path) -> anyhow::Result<()> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("failed to load {}", path.display()))?;
+        let saved: Session = serde_json::from_str(&contents)
+            .with_context(|| format!("{} isn't a saved session", path.display()))?;
+
+        let mut functions = HashMap::new();
+        for definition in &saved.definitions {
+            if let Ok(Statement::Function(function)) =
+                Parser::new(&definition.source).parse_statement()
+            {
+                functions.insert(function.name.clone(), function);
+            }
+        }
+
+        *self = Session {
+            functions,
+            rates: mem::take(&mut self.rates),
+            rng: mem::take(&mut self.rng),
+            ..saved
+        };
+
+        Ok(())
+    }
+
+    /// Forgets every variable and function, but keeps the settings.
+    pub fn clear(&mut self) {
+        self.variables.clear();
+        self.functions.clear();
+        self.definitions.clear();
+    }
+
+    /// The variables by name, with their values written as answers are.
+    pub fn variables(&self) -> Vec<(&str, String)> {
+        let mut variables = self
+            .variables
+            .iter()
+            .map(|(name, value)| (name.as_str(), format::answer(value, &self.settings)))
+            .collect::<Vec<_>>();
+
+        variables.sort();
+        variables
+    }
+
+    /// The functions by name, as they were defined.
+    pub fn functions(&self) -> Vec<&str> {
+        let mut functions = self
+            .functions
+            .keys()
+            .filter_map(|name| {
+                self.definitions
+                    .iter()
+                    .rev()
+                    .find(|definition| &definition.name == name)
+                    .map(|definition| definition.source.as_str())
+            })
+            .collect::<Vec<_>>();
+
+        functions.sort();
+        functions
+    }
+
+    /// What the answer to `source` would be, without defining anything or using up
+    /// any random numbers, if it can be worked out.
+    pub fn preview(&self, source: &str) -> Option<String> {
+        let expr = match Parser::new(source).parse_statement().ok()? {
+            Statement::Expr(expr) => expr,
+            Statement::Assign { value, .. } => value,
+            Statement::Function(_) => return None,
+        };
+
+        let rng = self.rng.borrow().clone();
+        let value = eval_expr(&expr, &Scope::global(self));
+        *self.rng.borrow_mut() = rng;
+
+        Some(format::answer(&value.ok()?, &self.settings))
+    }
+
+    /// Every name that means something already: built in functions, constants,
+    /// units, currencies, and the variables and functions defined so far.
+    pub fn names(&self) -> Vec<&str> {
+        let mut names = builtins::NAMES
+            .iter()
+            .copied()
+            .chain(CONSTANTS)
+            .chain(units::names())
+            .chain(currency::names())
+            .map(|name| -> &str { name })
+            .chain(self.variables.keys().map(String::as_str))
+            .chain(self.functions.keys().map(String::as_str))
+            .collect::<Vec<_>>();
+
+        names.sort_unstable();
+        names.dedup();
+        names
+    }
+
+    /// The latest inputs, oldest first, with what they came to.
+    pub fn history(&self) -> &[Exchange] {
+        &self.history
+    }
+}
+
+/// What an expression can see while it's evaluated.
+pub struct Scope<'a> {
+    session: &'a Session,
+    /// Arguments of the function being called, which along with the session's variables
+    /// is all its body can refer to.
+    locals: HashMap<&'a str, Value>,
+    depth: usize,
+}
+
+impl<'a> Scope<'a> {
+    fn global(session: &'a Session) -> Self {
+        Self {
+            session,
+            locals: HashMap::new(),
+            depth: 0,
+        }
+    }
+
+    pub fn variable(&self, name: &str) -> Option<&Value> {
+        self.locals
+            .get(name)
+            .or_else(|| self.session.variables.get(name))
+    }
+
+    /// A function's parameter, or the unknown or counter of an equation, derivative
+    /// or sum, which unlike the session's variables can be named like a unit.
+    pub fn local(&self, name: &str) -> Option<&Value> {
+        self.locals.get(name)
+    }
+
+    pub fn function(&self, name: &str) -> Option<&'a FunctionDef> {
+        self.session.functions.get(name)
+    }
+
+    pub fn rates(&self) -> &'a Rates {
+        &self.session.rates
+    }
+
+    pub fn settings(&self) -> &'a Settings {
+        &self.session.settings
+    }
+
+    pub fn rng(&self) -> RefMut<'a, Rng> {
+        self.session.rng.borrow_mut()
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// This scope, with `name` standing for `value`, as the unknown of an equation
+    /// does while it's being solved.
+    pub fn with_variable<'b>(&self, name: &'b str, value: Value) -> Scope<'b>
+    where
+        'a: 'b,
+    {
+        let mut locals: HashMap<&'b str, Value> = self
+            .locals
+            .iter()
+            .map(|(name, value)| (*name, value.clone()))
+            .collect();
+        locals.insert(name, value);
+
+        Scope {
+            session: self.session,
+            locals,
+            depth: self.depth,
+        }
+    }
+
+    /// The scope a function's body is evaluated in, with its parameters bound to `args`.
+    pub fn call(&self, function: &'a FunctionDef, args: Vec<Value>) -> Scope<'a> {
+        Scope {
+            session: self.session,
+            locals: function
+                .params
+                .iter()
+                .map(String::as_str)
+                .zip(args)
+                .collect(),
+            depth: self.depth + 1,
+        }
+    }
+}
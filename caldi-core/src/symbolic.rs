@@ -0,0 +1,418 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+/// An expression that's still got unknowns in it, kept in a simplified form.
+///
+/// Differences are sums with negated terms, and quotients products with
+/// factors raised to negative powers, so there's fewer cases to simplify.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Term {
+    Number(f64),
+    Symbol(String),
+    /// Terms added together.
+    Sum(Vec<Term>),
+    /// Factors multiplied together.
+    Product(Vec<Term>),
+    Power(Box<Term>, Box<Term>),
+}
+
+impl Term {
+    pub(crate) fn add(self, other: Term) -> Term {
+        Term::Sum(vec![self, other]).simplify()
+    }
+
+    pub(crate) fn sub(self, other: Term) -> Term {
+        Term::Sum(vec![self, other.neg()]).simplify()
+    }
+
+    pub(crate) fn mul(self, other: Term) -> Term {
+        Term::Product(vec![self, other]).simplify()
+    }
+
+    pub(crate) fn div(self, other: Term) -> Term {
+        Term::Product(vec![self, power(other, Term::Number(-1.0))]).simplify()
+    }
+
+    pub(crate) fn neg(self) -> Term {
+        Term::Product(vec![Term::Number(-1.0), self]).simplify()
+    }
+
+    pub fn pow(self, exponent: Term) -> Term {
+        power(self, exponent).simplify()
+    }
+
+    /// Folds constants, gathers like terms and factors, and drops what does nothing,
+    /// like adding 0 or raising to the power of 1.
+    pub fn simplify(self) -> Term {
+        match self {
+            Term::Sum(terms) => simplify_sum(terms),
+            Term::Product(factors) => simplify_product(factors),
+            Term::Power(base, exponent) => simplify_power(base.simplify(), exponent.simplify()),
+            term => term,
+        }
+    }
+
+    /// Whether `name` appears anywhere in it.
+    pub fn contains(&self, name: &str) -> bool {
+        match self {
+            Term::Number(_) => false,
+            Term::Symbol(symbol) => symbol == name,
+            Term::Sum(terms) | Term::Product(terms) => terms.iter().any(|t| t.contains(name)),
+            Term::Power(base, exponent) => base.contains(name) || exponent.contains(name),
+        }
+    }
+
+    /// The derivative with respect to `name`, simplified. There's none for powers
+    /// with `name` in both the base and the exponent, as that takes logarithms of
+    /// unknowns.
+    pub fn derivative(&self, name: &str) -> Option<Term> {
+        let derivative = match self {
+            Term::Number(_) => Term::Number(0.0),
+            Term::Symbol(symbol) => Term::Number(if symbol == name { 1.0 } else { 0.0 }),
+            Term::Sum(terms) => Term::Sum(
+                terms
+                    .iter()
+                    .map(|term| term.derivative(name))
+                    .collect::<Option<_>>()?,
+            ),
+            // the product rule
+            Term::Product(factors) => {
+                let mut terms = vec![];
+
+                for (i, factor) in factors.iter().enumerate() {
+                    let mut product = factors.clone();
+                    product[i] = factor.derivative(name)?;
+                    terms.push(Term::Product(product));
+                }
+
+                Term::Sum(terms)
+            }
+            // the power rule, along with the chain rule
+            Term::Power(base, exponent) if !exponent.contains(name) => Term::Product(vec![
+                (**exponent).clone(),
+                power(
+                    (**base).clone(),
+                    Term::Sum(vec![(**exponent).clone(), Term::Number(-1.0)]),
+                ),
+                base.derivative(name)?,
+            ]),
+            // a constant to the power of the unknown
+            Term::Power(base, exponent) => match **base {
+                Term::Number(b) => Term::Product(vec![
+                    self.clone(),
+                    Term::Number(b.ln()),
+                    exponent.derivative(name)?,
+                ]),
+                _ => return None,
+            },
+        };
+
+        Some(derivative.simplify())
+    }
+
+    /// How many of its unknowns are multiplied together, for putting the terms of a
+    /// sum in the usual order, highest power first.
+    fn degree(&self) -> f64 {
+        match self {
+            Term::Number(_) => 0.0,
+            Term::Symbol(_) => 1.0,
+            Term::Sum(terms) => terms.iter().map(Term::degree).fold(0.0, f64::max),
+            Term::Product(factors) => factors.iter().map(Term::degree).sum(),
+            Term::Power(base, exponent) => match **exponent {
+                Term::Number(e) => base.degree() * e,
+                _ => base.degree(),
+            },
+        }
+    }
+
+    /// Whether it'd be written with a minus in front.
+    fn is_negative(&self) -> bool {
+        match self {
+            Term::Number(n) => *n < 0.0,
+            Term::Product(factors) => matches!(factors.first(), Some(Term::Number(n)) if *n < 0.0),
+            _ => false,
+        }
+    }
+
+    /// Splits off the number it's multiplied by, as in the 3 of `3x`.
+    fn coefficient(self) -> (f64, Term) {
+        match self {
+            Term::Number(n) => (n, Term::Number(1.0)),
+            Term::Product(mut factors) => match factors.first() {
+                Some(Term::Number(n)) => {
+                    let n = *n;
+                    factors.remove(0);
+                    let rest = match factors.len() {
+                        1 => factors.remove(0),
+                        _ => Term::Product(factors),
+                    };
+                    (n, rest)
+                }
+                _ => (1.0, Term::Product(factors)),
+            },
+            term => (1.0, term),
+        }
+    }
+}
+
+fn power(base: Term, exponent: Term) -> Term {
+    Term::Power(Box::new(base), Box::new(exponent))
+}
+
+fn simplify_sum(terms: Vec<Term>) -> Term {
+    let mut constant = 0.0;
+    // like terms, by what's left of them without their coefficients
+    let mut like: Vec<(Term, f64)> = vec![];
+
+    for term in terms.into_iter().flat_map(|term| match term.simplify() {
+        Term::Sum(terms) => terms,
+        term => vec![term],
+    }) {
+        if let Term::Number(n) = term {
+            constant += n;
+            continue;
+        }
+
+        let (coefficient, rest) = term.coefficient();
+
+        match like.iter_mut().find(|(other, _)| *other == rest) {
+            Some((_, total)) => *total += coefficient,
+            None => like.push((rest, coefficient)),
+        }
+    }
+
+    let mut terms = like
+        .into_iter()
+        .filter(|(_, coefficient)| *coefficient != 0.0)
+        .map(|(term, coefficient)| {
+            if coefficient == 1.0 {
+                term
+            } else {
+                simplify_product(vec![Term::Number(coefficient), term])
+            }
+        })
+        .collect::<Vec<_>>();
+
+    terms.sort_by(|a, b| b.degree().total_cmp(&a.degree()));
+
+    if constant != 0.0 {
+        terms.push(Term::Number(constant));
+    }
+
+    match terms.len() {
+        0 => Term::Number(0.0),
+        1 => terms.remove(0),
+        _ => Term::Sum(terms),
+    }
+}
+
+fn simplify_product(factors: Vec<Term>) -> Term {
+    let mut coefficient = 1.0;
+    // factors with the same base, by their exponents
+    let mut powers: Vec<(Term, Vec<Term>)> = vec![];
+
+    for factor in factors
+        .into_iter()
+        .flat_map(|factor| match factor.simplify() {
+            Term::Product(factors) => factors,
+            factor => vec![factor],
+        })
+    {
+        let (base, exponent) = match factor {
+            Term::Number(n) => {
+                coefficient *= n;
+                continue;
+            }
+            Term::Power(base, exponent) => (*base, *exponent),
+            factor => (factor, Term::Number(1.0)),
+        };
+
+        match powers.iter_mut().find(|(other, _)| *other == base) {
+            Some((_, exponents)) => exponents.push(exponent),
+            None => powers.push((base, vec![exponent])),
+        }
+    }
+
+    let mut factors = vec![];
+
+    for (base, exponents) in powers {
+        match simplify_power(base, simplify_sum(exponents)) {
+            Term::Number(n) => coefficient *= n,
+            Term::Product(more) => {
+                for factor in more {
+                    match factor {
+                        Term::Number(n) => coefficient *= n,
+                        factor => factors.push(factor),
+                    }
+                }
+            }
+            factor => factors.push(factor),
+        }
+    }
+
+    if coefficient == 0.0 {
+        return Term::Number(0.0);
+    }
+
+    // a number times a sum is multiplied out, so that its terms can be gathered with others
+    if let [Term::Sum(terms)] = &factors[..] {
+        if coefficient != 1.0 {
+            return simplify_sum(
+                terms
+                    .iter()
+                    .map(|term| Term::Product(vec![Term::Number(coefficient), term.clone()]))
+                    .collect(),
+            );
+        }
+    }
+
+    // sums, and powers of them, go last, as in 2x * (x + 1)^2
+    factors.sort_by_key(|factor| match factor {
+        Term::Sum(_) => 1,
+        Term::Power(base, _) if matches!(**base, Term::Sum(_)) => 1,
+        _ => 0,
+    });
+
+    if coefficient != 1.0 || factors.is_empty() {
+        factors.insert(0, Term::Number(coefficient));
+    }
+
+    match factors.len() {
+        1 => factors.remove(0),
+        _ => Term::Product(factors),
+    }
+}
+
+fn simplify_power(base: Term, exponent: Term) -> Term {
+    let whole = |n: f64| n.fract() == 0.0;
+
+    match (base, exponent) {
+        (Term::Number(b), Term::Number(e)) if b.powf(e).is_finite() => Term::Number(b.powf(e)),
+        (_, Term::Number(0.0)) => Term::Number(1.0),
+        (base, Term::Number(1.0)) => base,
+        (Term::Number(1.0), _) => Term::Number(1.0),
+        (Term::Power(base, inner), Term::Number(e)) if whole(e) => {
+            simplify_power(*base, simplify_product(vec![*inner, Term::Number(e)]))
+        }
+        (Term::Product(factors), Term::Number(e)) if whole(e) => simplify_product(
+            factors
+                .into_iter()
+                .map(|factor| power(factor, Term::Number(e)))
+                .collect(),
+        ),
+        (base, exponent) => power(base, exponent),
+    }
+}
+
+impl Display for Term {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Term::Number(n) => write!(f, "{n}"),
+            Term::Symbol(name) => write!(f, "{name}"),
+            Term::Sum(terms) => {
+                for (i, term) in terms.iter().enumerate() {
+                    match (i, term.is_negative()) {
+                        (0, _) => write!(f, "{term}")?,
+                        (_, true) => write!(f, " - {}", term.clone().neg())?,
+                        (_, false) => write!(f, " + {term}")?,
+                    }
+                }
+                Ok(())
+            }
+            Term::Product(_) => write_product(f, self),
+            Term::Power(base, exponent) => match **exponent {
+                Term::Number(e) if e < 0.0 => {
+                    write!(f, "1/")?;
+                    write_factor(f, &simplify_power((**base).clone(), Term::Number(-e)))
+                }
+                Term::Number(0.5) => write!(f, "sqrt({base})"),
+                _ => {
+                    match **base {
+                        Term::Sum(_) | Term::Product(_) | Term::Power(..) => write!(f, "({base})")?,
+                        Term::Number(b) if b < 0.0 => write!(f, "({base})")?,
+                        _ => write!(f, "{base}")?,
+                    }
+                    match **exponent {
+                        Term::Symbol(_) => write!(f, "^{exponent}"),
+                        Term::Number(e) if e >= 0.0 => write!(f, "^{exponent}"),
+                        _ => write!(f, "^({exponent})"),
+                    }
+                }
+            },
+        }
+    }
+}
+
+/// Writes a product with a number in front, like `3x^2`, and anything raised to
+/// a negative power under a line, like `2/x`.
+fn write_product(f: &mut std::fmt::Formatter<'_>, product: &Term) -> std::fmt::Result {
+    let (coefficient, rest) = product.clone().coefficient();
+
+    let factors = match rest {
+        Term::Product(factors) => factors,
+        factor => vec![factor],
+    };
+
+    let (denominator, numerator): (Vec<_>, Vec<_>) = factors
+        .into_iter()
+        .partition(|factor| matches!(factor, Term::Power(_, e) if e.is_negative()));
+
+    let denominator = denominator
+        .into_iter()
+        .map(|factor| match factor {
+            Term::Power(base, exponent) => simplify_power(*base, exponent.neg()),
+            factor => factor,
+        })
+        .collect::<Vec<_>>();
+
+    match (coefficient, numerator.first()) {
+        (-1.0, Some(first)) if juxtaposes(first) => write!(f, "-")?,
+        (1.0, Some(first)) if juxtaposes(first) => {}
+        (c, Some(first)) if juxtaposes(first) => write!(f, "{c}")?,
+        (1.0, Some(_)) => {}
+        (c, Some(_)) => write!(f, "{c} * ")?,
+        (c, None) => write!(f, "{c}")?,
+    }
+
+    for (i, factor) in numerator.iter().enumerate() {
+        if i > 0 {
+            write!(f, " * ")?;
+        }
+        write_factor(f, factor)?;
+    }
+
+    match &denominator[..] {
+        [] => Ok(()),
+        [factor] => {
+            write!(f, "/")?;
+            write_factor(f, factor)
+        }
+        factors => {
+            write!(f, "/(")?;
+            for (i, factor) in factors.iter().enumerate() {
+                if i > 0 {
+                    write!(f, " * ")?;
+                }
+                write_factor(f, factor)?;
+            }
+            write!(f, ")")
+        }
+    }
+}
+
+/// Whether it can go right after a number with nothing in between, as `x^2` can in `3x^2`.
+fn juxtaposes(factor: &Term) -> bool {
+    match factor {
+        Term::Symbol(name) => name.chars().count() == 1,
+        Term::Power(base, _) => matches!(&**base, Term::Symbol(name) if name.chars().count() == 1),
+        _ => false,
+    }
+}
+
+fn write_factor(f: &mut std::fmt::Formatter<'_>, factor: &Term) -> std::fmt::Result {
+    match factor {
+        Term::Sum(_) | Term::Product(_) => write!(f, "({factor})"),
+        factor => write!(f, "{factor}"),
+    }
+}
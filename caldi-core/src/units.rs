@@ -0,0 +1,474 @@
+use std::{cmp::Ordering, fmt::Display};
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use super::value::{OperandError, Result, Value};
+
+/// Exponents of the SI base quantities (length, mass, time, temperature and
+/// electric current) that something is measured in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Dimension([i8; 5]);
+
+const LENGTH: Dimension = Dimension([1, 0, 0, 0, 0]);
+const AREA: Dimension = Dimension([2, 0, 0, 0, 0]);
+const VOLUME: Dimension = Dimension([3, 0, 0, 0, 0]);
+const MASS: Dimension = Dimension([0, 1, 0, 0, 0]);
+const TIME: Dimension = Dimension([0, 0, 1, 0, 0]);
+const TEMPERATURE: Dimension = Dimension([0, 0, 0, 1, 0]);
+const CURRENT: Dimension = Dimension([0, 0, 0, 0, 1]);
+const SPEED: Dimension = Dimension([1, 0, -1, 0, 0]);
+const ACCELERATION: Dimension = Dimension([1, 0, -2, 0, 0]);
+const FORCE: Dimension = Dimension([1, 1, -2, 0, 0]);
+const ENERGY: Dimension = Dimension([2, 1, -2, 0, 0]);
+const POWER: Dimension = Dimension([2, 1, -3, 0, 0]);
+const PRESSURE: Dimension = Dimension([-1, 1, -2, 0, 0]);
+const VOLTAGE: Dimension = Dimension([2, 1, -3, 0, -1]);
+const RESISTANCE: Dimension = Dimension([2, 1, -3, 0, -2]);
+
+const BASE_SYMBOLS: [&str; 5] = ["m", "kg", "s", "K", "A"];
+
+impl Dimension {
+    fn is_none(&self) -> bool {
+        self.0 == [0; 5]
+    }
+
+    fn combine(self, other: Dimension, sign: i8) -> Dimension {
+        let mut exponents = self.0;
+        for (e, o) in exponents.iter_mut().zip(other.0) {
+            *e += sign * o;
+        }
+        Dimension(exponents)
+    }
+
+    fn powi(self, n: i8) -> Dimension {
+        Dimension(self.0.map(|e| e * n))
+    }
+
+    /// A human name for what is being measured, like "length" or "speed".
+    pub fn describe(&self) -> String {
+        let name = match *self {
+            d if d.is_none() => "a plain number",
+            LENGTH => "length",
+            AREA => "area",
+            VOLUME => "volume",
+            MASS => "mass",
+            TIME => "time",
+            TEMPERATURE => "temperature",
+            CURRENT => "current",
+            SPEED => "speed",
+            ACCELERATION => "acceleration",
+            FORCE => "force",
+            ENERGY => "energy",
+            POWER => "power",
+            PRESSURE => "pressure",
+            VOLTAGE => "voltage",
+            RESISTANCE => "resistance",
+            _ => {
+                let powers = BASE_SYMBOLS.iter().copied().zip(self.0);
+                return format_powers(powers.filter(|(_, p)| *p != 0));
+            }
+        };
+
+        name.to_string()
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Unit {
+    pub symbol: &'static str,
+    /// Spelled out names, including plurals.
+    pub names: &'static [&'static str],
+    pub dimension: Dimension,
+    /// How many SI base units one of this is.
+    pub factor: f64,
+    /// Added before scaling into SI base units, for scales that don't start
+    /// at zero (i.e Celsius and Fahrenheit).
+    pub offset: f64,
+}
+
+macro_rules! units {
+    ($($symbol:literal [$($name:literal),*] $dimension:ident $factor:expr $(; $offset:expr)?),* $(,)?) => {
+        &[$(Unit {
+            symbol: $symbol,
+            names: &[$($name),*],
+            dimension: $dimension,
+            factor: $factor,
+            offset: 0.0 $(+ $offset)?,
+        }),*]
+    };
+}
+
+static UNITS: &[Unit] = units![
+    "m" ["meter", "meters", "metre", "metres"] LENGTH 1.0,
+    "km" ["kilometer", "kilometers", "kilometre", "kilometres"] LENGTH 1e3,
+    "cm" ["centimeter", "centimeters", "centimetre", "centimetres"] LENGTH 1e-2,
+    "mm" ["millimeter", "millimeters", "millimetre", "millimetres"] LENGTH 1e-3,
+    "mi" ["mile", "miles"] LENGTH 1609.344,
+    "yd" ["yard", "yards"] LENGTH 0.9144,
+    "ft" ["foot", "feet"] LENGTH 0.3048,
+    "inch" ["inches"] LENGTH 0.0254,
+    "nmi" ["nautical mile", "nautical miles"] LENGTH 1852.0,
+    "ha" ["hectare", "hectares"] AREA 1e4,
+    "acre" ["acres"] AREA 4046.8564224,
+    "l" ["L", "liter", "liters", "litre", "litres"] VOLUME 1e-3,
+    "ml" ["mL", "milliliter", "milliliters", "millilitre", "millilitres"] VOLUME 1e-6,
+    "gal" ["gallon", "gallons"] VOLUME 3.785411784e-3,
+    "cup" ["cups"] VOLUME 2.365882365e-4,
+    "floz" ["fluid ounce", "fluid ounces"] VOLUME 2.95735295625e-5,
+    "tbsp" ["tablespoon", "tablespoons"] VOLUME 1.478676478125e-5,
+    "tsp" ["teaspoon", "teaspoons"] VOLUME 4.92892159375e-6,
+    "kg" ["kilogram", "kilograms", "kilo", "kilos"] MASS 1.0,
+    "g" ["gram", "grams"] MASS 1e-3,
+    "mg" ["milligram", "milligrams"] MASS 1e-6,
+    "t" ["tonne", "tonnes"] MASS 1e3,
+    "lb" ["lbs", "pound", "pounds"] MASS 0.45359237,
+    "oz" ["ounce", "ounces"] MASS 0.028349523125,
+    "s" ["sec", "secs", "second", "seconds"] TIME 1.0,
+    "ms" ["millisecond", "milliseconds"] TIME 1e-3,
+    "min" ["mins", "minute", "minutes"] TIME 60.0,
+    "h" ["hr", "hrs", "hour", "hours"] TIME 3600.0,
+    "day" ["days"] TIME 86400.0,
+    "week" ["weeks"] TIME 604800.0,
+    "year" ["years"] TIME 31557600.0,
+    "K" ["kelvin", "kelvins"] TEMPERATURE 1.0,
+    "°C" ["C", "celsius", "degrees celsius"] TEMPERATURE 1.0; 273.15,
+    "°F" ["F", "fahrenheit", "degrees fahrenheit"] TEMPERATURE 5.0 / 9.0; 459.67,
+    "A" ["amp", "amps", "ampere", "amperes"] CURRENT 1.0,
+    "mph" ["miles per hour"] SPEED 0.44704,
+    "kph" ["kmh", "kilometers per hour", "kilometres per hour"] SPEED 1.0 / 3.6,
+    "knot" ["knots"] SPEED 1852.0 / 3600.0,
+    "N" ["newton", "newtons"] FORCE 1.0,
+    "lbf" ["pound force", "pounds force"] FORCE 4.4482216152605,
+    "J" ["joule", "joules"] ENERGY 1.0,
+    "kJ" ["kilojoule", "kilojoules"] ENERGY 1e3,
+    "cal" ["calorie", "calories"] ENERGY 4.184,
+    "kcal" ["kilocalorie", "kilocalories"] ENERGY 4184.0,
+    "Wh" ["watt hour", "watt hours"] ENERGY 3600.0,
+    "kWh" ["kilowatt hour", "kilowatt hours"] ENERGY 3.6e6,
+    "W" ["watt", "watts"] POWER 1.0,
+    "kW" ["kilowatt", "kilowatts"] POWER 1e3,
+    "hp" ["horsepower"] POWER 745.6998715822702,
+    "Pa" ["pascal", "pascals"] PRESSURE 1.0,
+    "kPa" ["kilopascal", "kilopascals"] PRESSURE 1e3,
+    "bar" ["bars"] PRESSURE 1e5,
+    "atm" ["atmosphere", "atmospheres"] PRESSURE 101325.0,
+    "psi" [] PRESSURE 6894.757293168,
+    "V" ["volt", "volts"] VOLTAGE 1.0,
+    "ohm" ["ohms"] RESISTANCE 1.0,
+];
+
+/// Units are saved as their symbol, and looked up again when they're read back.
+impl Serialize for Unit {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.symbol)
+    }
+}
+
+impl<'de> Deserialize<'de> for &'static Unit {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let symbol = String::deserialize(deserializer)?;
+        UNITS
+            .iter()
+            .find(|unit| unit.symbol == symbol)
+            .ok_or_else(|| de::Error::custom(format!("unknown unit {symbol}")))
+    }
+}
+
+/// Every unit's symbol and names.
+pub fn names() -> impl Iterator<Item = &'static str> {
+    UNITS
+        .iter()
+        .flat_map(|unit| std::iter::once(unit.symbol).chain(unit.names.iter().copied()))
+}
+
+/// Finds a unit by its symbol (which is case sensitive), or one of its names.
+pub fn lookup(name: &str) -> Option<&'static Unit> {
+    UNITS.iter().find(|unit| unit.symbol == name).or_else(|| {
+        let name = name.to_lowercase();
+        UNITS
+            .iter()
+            .find(|unit| unit.names.iter().any(|n| n.to_lowercase() == name))
+    })
+}
+
+/// An amount of something measured in units.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Quantity {
+    /// The amount in SI base units.
+    value: f64,
+    dimension: Dimension,
+    /// The units to show the amount in, with their powers.
+    units: Vec<(&'static Unit, i8)>,
+}
+
+impl Quantity {
+    pub(crate) fn new(magnitude: f64, unit: &'static Unit) -> Self {
+        Self {
+            value: (magnitude + unit.offset) * unit.factor,
+            dimension: unit.dimension,
+            units: vec![(unit, 1)],
+        }
+    }
+
+    fn plain(number: f64) -> Self {
+        Self {
+            value: number,
+            dimension: Dimension::default(),
+            units: vec![],
+        }
+    }
+
+    pub fn seconds(seconds: f64) -> Self {
+        Self::new(
+            seconds,
+            lookup("s").expect("seconds are in the table of units"),
+        )
+    }
+
+    pub(crate) fn dimension(&self) -> Dimension {
+        self.dimension
+    }
+
+    pub fn is_time(&self) -> bool {
+        self.dimension == TIME
+    }
+
+    /// The amount in SI base units.
+    pub fn base_value(&self) -> f64 {
+        self.value
+    }
+
+    pub fn has_same_units(&self, other: &Quantity) -> bool {
+        self.units == other.units
+    }
+
+    /// The single unit this is shown in, if it isn't a compound like m/s.
+    fn unit(&self) -> Option<&'static Unit> {
+        match self.units.as_slice() {
+            [(unit, 1)] => Some(unit),
+            _ => None,
+        }
+    }
+
+    /// The SI value of zero in this quantity's unit, which is only not zero for
+    /// temperature scales like Celsius.
+    fn zero_point(&self) -> f64 {
+        self.unit().map(|u| u.offset * u.factor).unwrap_or(0.0)
+    }
+
+    /// How many SI base units one of the units it's shown in is.
+    fn factor(&self) -> f64 {
+        self.units
+            .iter()
+            .map(|(unit, power)| unit.factor.powi(*power as i32))
+            .product()
+    }
+
+    /// The amount in the units it's shown in.
+    pub fn magnitude(&self) -> f64 {
+        match self.unit() {
+            Some(unit) => self.value / unit.factor - unit.offset,
+            None => self.value / self.factor(),
+        }
+    }
+
+    fn with_magnitude(&self, magnitude: f64) -> Self {
+        match self.unit() {
+            Some(unit) => Self::new(magnitude, unit),
+            None => Self {
+                value: magnitude * self.factor(),
+                ..self.clone()
+            },
+        }
+    }
+
+    fn expect_same_dimension(&self, other: &Quantity) -> Result<()> {
+        if self.dimension != other.dimension {
+            return Err(OperandError::IncompatibleUnits {
+                left: self.dimension.describe(),
+                right: other.dimension.describe(),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Adds `other`, treating it as a difference when it's on a scale like Celsius,
+    /// so that 10 °C + 5 °C is 15 °C.
+    pub(crate) fn add(self, other: Quantity) -> Result<Value> {
+        self.expect_same_dimension(&other)?;
+
+        let value = self.value + (other.value - other.zero_point());
+
+        Ok(Quantity { value, ..self }.into())
+    }
+
+    pub(crate) fn sub(self, other: Quantity) -> Result<Value> {
+        self.expect_same_dimension(&other)?;
+
+        let value = self.value - (other.value - other.zero_point());
+
+        Ok(Quantity { value, ..self }.into())
+    }
+
+    pub(crate) fn mul(self, other: Quantity) -> Value {
+        self.combine(other, 1)
+    }
+
+    pub(crate) fn div(self, other: Quantity) -> Value {
+        self.combine(other, -1)
+    }
+
+    fn combine(mut self, other: Quantity, sign: i8) -> Value {
+        let value = if sign > 0 {
+            self.value * other.value
+        } else {
+            self.value / other.value
+        };
+
+        let dimension = self.dimension.combine(other.dimension, sign);
+
+        if dimension.is_none() {
+            return value.into();
+        }
+
+        // a unit that measures exactly what the result does, like the hours in (3 h * 2 h) / 2 h
+        let whole = self
+            .units
+            .iter()
+            .chain(&other.units)
+            .map(|(unit, _)| *unit)
+            .find(|unit| unit.dimension == dimension && unit.offset == 0.0);
+
+        for (unit, power) in other.units {
+            // show a length times a length in the same unit of length, and so on
+            match self
+                .units
+                .iter_mut()
+                .find(|(u, _)| u.dimension == unit.dimension)
+            {
+                Some((_, p)) => *p += sign * power,
+                None => self.units.push((unit, sign * power)),
+            }
+        }
+
+        self.units.retain(|(_, power)| *power != 0);
+
+        if self.units.len() > 1 {
+            // or failing that the SI unit for it, like newtons for kg m/s^2
+            let si = || {
+                UNITS.iter().find(|unit| {
+                    unit.dimension == dimension && unit.factor == 1.0 && unit.offset == 0.0
+                })
+            };
+
+            if let Some(unit) = whole.or_else(si) {
+                self.units = vec![(unit, 1)];
+            }
+        }
+
+        Quantity {
+            value,
+            dimension,
+            units: self.units,
+        }
+        .into()
+    }
+
+    pub fn scale(self, factor: f64) -> Quantity {
+        self.with_magnitude(self.magnitude() * factor)
+    }
+
+    pub fn powi(self, n: i8) -> Value {
+        if n == 0 {
+            return 1.0.into();
+        }
+
+        Quantity {
+            value: self.value.powi(n as i32),
+            dimension: self.dimension.powi(n),
+            units: self.units.into_iter().map(|(u, p)| (u, p * n)).collect(),
+        }
+        .into()
+    }
+
+    /// The same amount, shown in the units of `target`.
+    pub fn convert(self, target: Quantity) -> Result<Quantity> {
+        self.expect_same_dimension(&target)?;
+
+        Ok(Quantity {
+            units: target.units,
+            ..self
+        })
+    }
+
+    pub fn compare(&self, other: &Quantity) -> Result<Option<Ordering>> {
+        self.expect_same_dimension(other)?;
+
+        Ok(self.value.partial_cmp(&other.value))
+    }
+
+    pub fn divide_into(self, number: f64) -> Value {
+        Quantity::plain(number).div(self)
+    }
+}
+
+impl Display for Quantity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let magnitude = round(self.magnitude());
+
+        // units with a word for a symbol, like days, read better in the plural
+        if let Some(unit) = self.unit() {
+            match unit.names.first() {
+                Some(plural) if magnitude != 1.0 && *plural == format!("{}s", unit.symbol) => {
+                    return write!(f, "{magnitude} {plural}");
+                }
+                _ => {}
+            }
+        }
+
+        let units = self.units.iter().map(|(unit, power)| (unit.symbol, *power));
+
+        write!(f, "{} {}", magnitude, format_powers(units))
+    }
+}
+
+/// Rounds off the noise that converting between units leaves in the last few digits.
+fn round(number: f64) -> f64 {
+    format!("{number:.11e}").parse().unwrap_or(number)
+}
+
+/// Writes something like `kg m/s^2`.
+fn format_powers<'a>(powers: impl Iterator<Item = (&'a str, i8)> + Clone) -> String {
+    let mut output = String::new();
+
+    for (symbol, power) in powers.clone().filter(|(_, p)| *p > 0) {
+        if !output.is_empty() {
+            output.push(' ');
+        }
+        output.push_str(symbol);
+        if power != 1 {
+            output.push_str(&format!("^{power}"));
+        }
+    }
+
+    if output.is_empty() {
+        output.push('1');
+    }
+
+    for (symbol, power) in powers.filter(|(_, p)| *p < 0) {
+        output.push('/');
+        output.push_str(symbol);
+        if power != -1 {
+            output.push_str(&format!("^{}", -power));
+        }
+    }
+
+    output
+}
+
+impl From<Quantity> for Value {
+    fn from(value: Quantity) -> Self {
+        Self::Quantity(value)
+    }
+}
@@ -0,0 +1,559 @@
+use std::{
+    cmp::Ordering,
+    fmt::Display,
+    ops::{Add, Div, Mul, Neg, Sub},
+};
+
+use chrono::{NaiveDate, NaiveTime};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    calendar::Duration,
+    complex::Complex,
+    currency::Money,
+    matrix::{Matrix, Shape},
+    parse::error::ErrorKind,
+    random::Coin,
+    symbolic::Term,
+    units::{Dimension, Quantity},
+};
+
+/// An answer, or something on the way to one.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Value {
+    Integer(isize),
+    Float(f64),
+    Bool(bool),
+    List(Vec<Value>),
+    Quantity(Quantity),
+    Money(Money),
+    Date(NaiveDate),
+    Time(NaiveTime),
+    Duration(Duration),
+    Complex(Complex),
+    Matrix(Matrix),
+    /// What's left of a calculation with unknowns in it.
+    Expression(Term),
+    Coin(Coin),
+}
+
+/// Why an operator couldn't be applied to its operands, before it's pinned
+/// to a position in the source.
+#[derive(Debug)]
+pub enum OperandError {
+    TypeMismatch {
+        expected: &'static str,
+        found: &'static str,
+    },
+    Overflow,
+    EmptyList,
+    IncompatibleUnits {
+        left: String,
+        right: String,
+    },
+    MissingRate {
+        from: &'static str,
+        to: &'static str,
+    },
+    WrongArgumentCount {
+        name: String,
+        expected: usize,
+        found: usize,
+    },
+    ShapeMismatch {
+        left: Shape,
+        right: Shape,
+    },
+    NotSquare {
+        shape: Shape,
+    },
+    Singular,
+    EmptyRange {
+        from: isize,
+        to: isize,
+    },
+}
+
+impl OperandError {
+    pub fn expected(expected: &'static str, found: &Value) -> Self {
+        Self::TypeMismatch {
+            expected,
+            found: found.type_name(),
+        }
+    }
+
+    fn incompatible_units(left: &Value, right: &Value) -> Self {
+        let describe = |value: &Value| match value {
+            Value::Quantity(q) => q.dimension().describe(),
+            Value::Money(_) => "money".to_string(),
+            Value::Duration(_) => "time".to_string(),
+            Value::Date(_) => "a date".to_string(),
+            Value::Time(_) => "a time of day".to_string(),
+            _ => Dimension::default().describe(),
+        };
+
+        Self::IncompatibleUnits {
+            left: describe(left),
+            right: describe(right),
+        }
+    }
+
+    /// Picks whichever of the operands isn't a number to complain about.
+    fn expected_numbers(left: &Value, right: &Value) -> Self {
+        let found = if left.is_number() { right } else { left };
+        Self::expected("number", found)
+    }
+
+    pub fn at(self, at: usize) -> ErrorKind {
+        match self {
+            OperandError::TypeMismatch { expected, found } => ErrorKind::TypeMismatch {
+                expected,
+                found,
+                at,
+            },
+            OperandError::Overflow => ErrorKind::Overflow { at },
+            OperandError::EmptyList => ErrorKind::EmptyList { at },
+            OperandError::IncompatibleUnits { left, right } => {
+                ErrorKind::IncompatibleUnits { left, right, at }
+            }
+            OperandError::MissingRate { from, to } => ErrorKind::MissingRate { from, to, at },
+            OperandError::WrongArgumentCount {
+                name,
+                expected,
+                found,
+            } => ErrorKind::WrongArgumentCount {
+                name,
+                expected,
+                found,
+                at,
+            },
+            OperandError::ShapeMismatch { left, right } => ErrorKind::ShapeMismatch {
+                left,
+                right,
+                operands: None,
+                at,
+            },
+            OperandError::NotSquare { shape } => ErrorKind::NotSquare { shape, at },
+            OperandError::Singular => ErrorKind::Singular { at },
+            OperandError::EmptyRange { from, to } => ErrorKind::EmptyRange { from, to, at },
+        }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, OperandError>;
+
+impl Add for Value {
+    type Output = Result<Value>;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        let value = match (self, rhs) {
+            (Value::Integer(l), Value::Integer(r)) => {
+                l.checked_add(r).ok_or(OperandError::Overflow)?.into()
+            }
+            (Value::Integer(l), Value::Float(r)) => (l as f64 + r).into(),
+            (Value::Float(l), Value::Integer(r)) => (l + r as f64).into(),
+            (Value::Float(l), Value::Float(r)) => (l + r).into(),
+            (l, r) if l.is_complex_with(&r) => l.as_complex().add(r.as_complex()).into(),
+            (l, r) if l.is_expression_with(&r) => l.into_term()?.add(r.into_term()?).into(),
+            // said in parts, like 3 hours 20 minutes
+            (Value::Quantity(l), Value::Quantity(r))
+                if l.is_time() && r.is_time() && !l.has_same_units(&r) =>
+            {
+                Duration::from_seconds(l.base_value() + r.base_value()).into()
+            }
+            (Value::Quantity(l), Value::Quantity(r)) => l.add(r)?,
+            (Value::Money(l), Value::Money(r)) => l.add(r)?.into(),
+            (Value::Matrix(l), Value::Matrix(r)) => l.add(&r)?.into(),
+            (Value::Date(d), t) | (t, Value::Date(d)) if t.is_duration() => {
+                t.as_duration().after_date(d)?.into()
+            }
+            (Value::Time(time), t) | (t, Value::Time(time)) if t.is_duration() => {
+                t.as_duration().after_time(time)?.into()
+            }
+            (l, r) if l.is_duration() && r.is_duration() => {
+                Duration::from_seconds(l.as_duration().seconds() + r.as_duration().seconds()).into()
+            }
+            (l, r) if l.has_units() || r.has_units() => {
+                return Err(OperandError::incompatible_units(&l, &r))
+            }
+            (l, r) => return Err(OperandError::expected_numbers(&l, &r)),
+        };
+
+        Ok(value)
+    }
+}
+
+impl Sub for Value {
+    type Output = Result<Value>;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        let value = match (self, rhs) {
+            (Value::Integer(l), Value::Integer(r)) => {
+                l.checked_sub(r).ok_or(OperandError::Overflow)?.into()
+            }
+            (Value::Integer(l), Value::Float(r)) => (l as f64 - r).into(),
+            (Value::Float(l), Value::Integer(r)) => (l - r as f64).into(),
+            (Value::Float(l), Value::Float(r)) => (l - r).into(),
+            (l, r) if l.is_complex_with(&r) => l.as_complex().sub(r.as_complex()).into(),
+            (l, r) if l.is_expression_with(&r) => l.into_term()?.sub(r.into_term()?).into(),
+            (Value::Quantity(l), Value::Quantity(r))
+                if l.is_time() && r.is_time() && !l.has_same_units(&r) =>
+            {
+                Duration::from_seconds(l.base_value() - r.base_value()).into()
+            }
+            (Value::Quantity(l), Value::Quantity(r)) => l.sub(r)?,
+            (Value::Money(l), Value::Money(r)) => l.sub(r)?.into(),
+            (Value::Matrix(l), Value::Matrix(r)) => l.sub(&r)?.into(),
+            (Value::Date(l), Value::Date(r)) => {
+                Duration::from_seconds((l - r).num_days() as f64 * 86400.0).into()
+            }
+            (Value::Date(d), t) if t.is_duration() => {
+                t.as_duration().scale(-1.0).after_date(d)?.into()
+            }
+            (Value::Time(l), Value::Time(r)) => {
+                Duration::from_seconds((l - r).num_milliseconds() as f64 / 1000.0).into()
+            }
+            (Value::Time(time), t) if t.is_duration() => {
+                t.as_duration().scale(-1.0).after_time(time)?.into()
+            }
+            (l, r) if l.is_duration() && r.is_duration() => {
+                Duration::from_seconds(l.as_duration().seconds() - r.as_duration().seconds()).into()
+            }
+            (l, r) if l.has_units() || r.has_units() => {
+                return Err(OperandError::incompatible_units(&l, &r))
+            }
+            (l, r) => return Err(OperandError::expected_numbers(&l, &r)),
+        };
+
+        Ok(value)
+    }
+}
+
+impl Mul for Value {
+    type Output = Result<Value>;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        let value = match (self, rhs) {
+            (Value::Integer(l), Value::Integer(r)) => {
+                l.checked_mul(r).ok_or(OperandError::Overflow)?.into()
+            }
+            (Value::Integer(l), Value::Float(r)) => (l as f64 * r).into(),
+            (Value::Float(l), Value::Integer(r)) => (l * r as f64).into(),
+            (Value::Float(l), Value::Float(r)) => (l * r).into(),
+            (l, r) if l.is_complex_with(&r) => l.as_complex().mul(r.as_complex()).into(),
+            (l, r) if l.is_expression_with(&r) => l.into_term()?.mul(r.into_term()?).into(),
+            (Value::Quantity(l), Value::Quantity(r)) => l.mul(r),
+            (Value::Quantity(q), n) | (n, Value::Quantity(q)) if n.is_number() => {
+                q.scale(n.as_f64()).into()
+            }
+            (Value::Money(m), n) | (n, Value::Money(m)) if n.is_number() => {
+                m.scale(n.as_f64()).into()
+            }
+            (Value::Duration(d), n) | (n, Value::Duration(d)) if n.is_number() => {
+                d.scale(n.as_f64()).into()
+            }
+            (Value::Matrix(l), Value::Matrix(r)) => l.mul(&r)?.into(),
+            (Value::Matrix(m), n) | (n, Value::Matrix(m)) if n.is_number() => {
+                m.scale(n.as_f64()).into()
+            }
+            // a list of numbers on the right is a column vector
+            (Value::Matrix(m), Value::List(items)) if Matrix::column(&items).is_some() => {
+                let column = Matrix::column(&items).expect("checked to be a column");
+                m.mul(&column)?.into_list()
+            }
+            (Value::Duration(d), r) if r.has_units() => (Value::Quantity(d.into_quantity()) * r)?,
+            (l, Value::Duration(d)) if l.has_units() => (l * Value::Quantity(d.into_quantity()))?,
+            (l, r) => return Err(OperandError::expected_numbers(&l, &r)),
+        };
+
+        Ok(value)
+    }
+}
+
+impl Div for Value {
+    type Output = Result<Value>;
+
+    fn div(self, rhs: Self) -> Self::Output {
+        let value = match (self, rhs) {
+            (Value::Integer(l), Value::Integer(r)) => (l as f64 / r as f64).into(),
+            (Value::Integer(l), Value::Float(r)) => (l as f64 / r).into(),
+            (Value::Float(l), Value::Integer(r)) => (l / r as f64).into(),
+            (Value::Float(l), Value::Float(r)) => (l / r).into(),
+            (l, r) if l.is_complex_with(&r) => l.as_complex().div(r.as_complex()).into(),
+            (l, r) if l.is_expression_with(&r) => l.into_term()?.div(r.into_term()?).into(),
+            (Value::Quantity(l), Value::Quantity(r)) => l.div(r),
+            (Value::Quantity(q), n) if n.is_number() => q.scale(1.0 / n.as_f64()).into(),
+            (n, Value::Quantity(q)) if n.is_number() => q.divide_into(n.as_f64()),
+            (Value::Money(l), Value::Money(r)) => l.ratio(&r)?,
+            (Value::Money(m), n) if n.is_number() => m.scale(1.0 / n.as_f64()).into(),
+            (Value::Duration(d), n) if n.is_number() => d.scale(1.0 / n.as_f64()).into(),
+            (Value::Matrix(m), n) if n.is_number() => m.scale(1.0 / n.as_f64()).into(),
+            (l, r) if l.is_duration() && r.is_duration() => {
+                (l.as_duration().seconds() / r.as_duration().seconds()).into()
+            }
+            (Value::Duration(d), r) if r.has_units() => (Value::Quantity(d.into_quantity()) / r)?,
+            (l, Value::Duration(d)) if l.has_units() || l.is_number() => {
+                (l / Value::Quantity(d.into_quantity()))?
+            }
+            (l, r) => return Err(OperandError::expected_numbers(&l, &r)),
+        };
+
+        Ok(value)
+    }
+}
+
+impl Neg for Value {
+    type Output = Result<Value>;
+
+    fn neg(self) -> Self::Output {
+        let value = match self {
+            Value::Integer(number) => number.checked_neg().ok_or(OperandError::Overflow)?.into(),
+            Value::Float(number) => (-number).into(),
+            Value::Quantity(q) => q.scale(-1.0).into(),
+            Value::Money(m) => m.scale(-1.0).into(),
+            Value::Duration(d) => d.scale(-1.0).into(),
+            Value::Complex(c) => c.neg().into(),
+            Value::Matrix(m) => m.scale(-1.0).into(),
+            Value::Expression(t) => t.neg().into(),
+            other => return Err(OperandError::expected("number", &other)),
+        };
+
+        Ok(value)
+    }
+}
+
+impl Value {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Integer(_) => "integer",
+            Value::Float(_) => "float",
+            Value::Bool(_) => "boolean",
+            Value::List(_) => "list",
+            Value::Quantity(_) => "quantity",
+            Value::Money(_) => "money",
+            Value::Date(_) => "date",
+            Value::Time(_) => "time",
+            Value::Duration(_) => "duration",
+            Value::Complex(_) => "complex number",
+            Value::Matrix(_) => "matrix",
+            Value::Expression(_) => "expression",
+            Value::Coin(_) => "coin flip",
+        }
+    }
+
+    pub fn is_number(&self) -> bool {
+        matches!(self, Value::Integer(_) | Value::Float(_))
+    }
+
+    fn has_units(&self) -> bool {
+        matches!(
+            self,
+            Value::Quantity(_) | Value::Money(_) | Value::Duration(_)
+        )
+    }
+
+    /// Whether either is a complex number, and the other is some kind of number too.
+    fn is_complex_with(&self, other: &Value) -> bool {
+        let is_complex = |v: &Value| matches!(v, Value::Complex(_));
+        let is_scalar = |v: &Value| v.is_number() || is_complex(v);
+
+        (is_complex(self) || is_complex(other)) && is_scalar(self) && is_scalar(other)
+    }
+
+    /// Whether either has unknowns in it, and the other is an expression or number too.
+    fn is_expression_with(&self, other: &Value) -> bool {
+        let is_expression = |v: &Value| matches!(v, Value::Expression(_));
+        let is_term = |v: &Value| v.is_number() || is_expression(v);
+
+        (is_expression(self) || is_expression(other)) && is_term(self) && is_term(other)
+    }
+
+    /// A number, or expression, for working with symbolically.
+    pub fn into_term(self) -> Result<Term> {
+        match self {
+            Value::Expression(t) => Ok(t),
+            n if n.is_number() => Ok(Term::Number(n.as_f64())),
+            other => Err(OperandError::expected("expression", &other)),
+        }
+    }
+
+    pub fn as_complex(&self) -> Complex {
+        match self {
+            Value::Complex(c) => *c,
+            other => Complex::new(other.as_f64(), 0.0),
+        }
+    }
+
+    /// Whether it's a span of time, be it a duration or an amount in units of time.
+    fn is_duration(&self) -> bool {
+        match self {
+            Value::Duration(_) => true,
+            Value::Quantity(q) => q.is_time(),
+            _ => false,
+        }
+    }
+
+    fn as_duration(&self) -> Duration {
+        match self {
+            Value::Duration(d) => *d,
+            Value::Quantity(q) => Duration::from_seconds(q.base_value()),
+            _ => Duration::from_seconds(f64::NAN),
+        }
+    }
+
+    pub fn as_f64(&self) -> f64 {
+        match self {
+            Value::Integer(i) => *i as f64,
+            Value::Float(f) => *f,
+            _ => f64::NAN,
+        }
+    }
+
+    pub fn into_integer(self) -> Result<isize> {
+        match self {
+            Value::Integer(number) => Ok(number),
+            other => Err(OperandError::expected("integer", &other)),
+        }
+    }
+
+    pub fn into_bool(self) -> Result<bool> {
+        match self {
+            Value::Bool(b) => Ok(b),
+            other => Err(OperandError::expected("boolean", &other)),
+        }
+    }
+
+    pub fn pow(self, rhs: Value) -> Result<Value> {
+        let value = match (self, rhs) {
+            (Value::Integer(l), Value::Integer(r)) if r >= 0 => u32::try_from(r)
+                .ok()
+                .and_then(|r| l.checked_pow(r))
+                .ok_or(OperandError::Overflow)?
+                .into(),
+            (Value::Integer(l), Value::Integer(r)) => (l as f64).powf(r as f64).into(),
+            (Value::Integer(l), Value::Float(r)) => (l as f64).powf(r).into(),
+            (Value::Float(l), Value::Integer(r)) => l.powf(r as f64).into(),
+            (Value::Float(l), Value::Float(r)) => l.powf(r).into(),
+            (Value::Complex(l), Value::Integer(r)) => l
+                .powi(i32::try_from(r).map_err(|_| OperandError::Overflow)?)
+                .into(),
+            (l, r) if l.is_complex_with(&r) => l.as_complex().powc(r.as_complex()).into(),
+            (l, r) if l.is_expression_with(&r) => l.into_term()?.pow(r.into_term()?).into(),
+            (Value::Matrix(l), Value::Integer(r)) => l.powi(r)?.into(),
+            (Value::Quantity(l), Value::Integer(r)) => {
+                l.powi(i8::try_from(r).map_err(|_| OperandError::Overflow)?)
+            }
+            (l, r) => return Err(OperandError::expected_numbers(&l, &r)),
+        };
+
+        Ok(value)
+    }
+
+    /// Multiplies matrices entry by entry, and anything else as usual.
+    pub fn elementwise_mul(self, rhs: Value) -> Result<Value> {
+        match (self, rhs) {
+            (Value::Matrix(l), Value::Matrix(r)) => Ok(l.elementwise_mul(&r)?.into()),
+            (l, r) => l * r,
+        }
+    }
+
+    /// Orders two numbers, regardless of whether they are integers or floats.
+    pub fn compare(&self, other: &Value) -> Result<Option<Ordering>> {
+        match (self, other) {
+            (Value::Integer(l), Value::Integer(r)) => Ok(l.partial_cmp(r)),
+            (Value::Integer(l), Value::Float(r)) => Ok((*l as f64).partial_cmp(r)),
+            (Value::Float(l), Value::Integer(r)) => Ok(l.partial_cmp(&(*r as f64))),
+            (Value::Float(l), Value::Float(r)) => Ok(l.partial_cmp(r)),
+            (Value::Quantity(l), Value::Quantity(r)) => l.compare(r),
+            (Value::Money(l), Value::Money(r)) => l.compare(r),
+            (Value::Date(l), Value::Date(r)) => Ok(l.partial_cmp(r)),
+            (Value::Time(l), Value::Time(r)) => Ok(l.partial_cmp(r)),
+            (l, r) if l.is_duration() && r.is_duration() => {
+                Ok(l.as_duration().compare(&r.as_duration()))
+            }
+            (l, r) if l.has_units() || r.has_units() => Err(OperandError::incompatible_units(l, r)),
+            (l, r) => Err(OperandError::expected_numbers(l, r)),
+        }
+    }
+
+    /// Shows the quantity on the left in the units of the one on the right.
+    pub fn convert(self, target: Value) -> Result<Value> {
+        match (self, target) {
+            (Value::Quantity(l), Value::Quantity(r)) => Ok(l.convert(r)?.into()),
+            (Value::Duration(d), Value::Quantity(r)) => Ok(d.into_quantity().convert(r)?.into()),
+            (l, r) => Err(OperandError::incompatible_units(&l, &r)),
+        }
+    }
+
+    pub fn equals(&self, other: &Value) -> Result<bool> {
+        match (self, other) {
+            (Value::Bool(l), Value::Bool(r)) => Ok(l == r),
+            (l, r) if l.is_complex_with(r) => Ok(l.as_complex() == r.as_complex()),
+            (l, r) => Ok(l.compare(r)? == Some(Ordering::Equal)),
+        }
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Integer(number) => write!(f, "{number}"),
+            Value::Float(number) => write!(f, "{number}"),
+            Value::Bool(true) => write!(f, "yes"),
+            Value::Bool(false) => write!(f, "no"),
+            Value::List(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{item}")?;
+                }
+                write!(f, "]")
+            }
+            Value::Quantity(q) => write!(f, "{q}"),
+            Value::Money(m) => write!(f, "{m}"),
+            Value::Date(date) => write!(f, "{}", date.format("%A, %B %-d, %Y")),
+            Value::Time(time) => write!(f, "{}", time.format("%-I:%M %p")),
+            Value::Duration(d) => write!(f, "{d}"),
+            Value::Complex(c) => write!(f, "{c}"),
+            Value::Matrix(m) => write!(f, "{m}"),
+            Value::Expression(t) => write!(f, "{t}"),
+            Value::Coin(coin) => write!(f, "{coin}"),
+        }
+    }
+}
+
+impl From<isize> for Value {
+    fn from(value: isize) -> Self {
+        Self::Integer(value)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Self::Float(value)
+    }
+}
+
+impl From<Vec<Value>> for Value {
+    fn from(value: Vec<Value>) -> Self {
+        Self::List(value)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+
+impl From<Term> for Value {
+    /// Expressions that have come out as a number are just numbers again.
+    fn from(value: Term) -> Self {
+        match value {
+            Term::Number(n) => Self::Float(n),
+            term => Self::Expression(term),
+        }
+    }
+}
@@ -0,0 +1,270 @@
+use std::{
+    collections::HashSet,
+    fs,
+    io::{self, BufRead},
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use anyhow::Context;
+use caldi_core::{
+    parse::error::ErrorKind,
+    render_error, render_script_error,
+    script::{self, Script},
+    Session,
+};
+
+use crate::Output;
+
+/// Answers each of `sources` in turn, printing only the answers, so they can be
+/// read by another program. Errors go to stderr, without stopping the rest from
+/// being answered, and make the exit code a failure.
+pub fn eval<'a>(
+    session: &mut Session,
+    sources: impl IntoIterator<Item = &'a str>,
+    output: Output,
+) -> ExitCode {
+    let mut exit_code = ExitCode::SUCCESS;
+    for source in sources {
+        if !answer(session, source, output) {
+            exit_code = ExitCode::FAILURE;
+        }
+    }
+
+    exit_code
+}
+
+/// Runs the script at `path`, printing the answers to its statements as [`eval`]
+/// does, with errors pointed out by file, line and column.
+pub fn run_file(session: &mut Session, path: &Path, output: Output) -> anyhow::Result<ExitCode> {
+    let script = Script::load(path)?;
+    let mut imported = HashSet::new();
+    imported.insert(fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf()));
+
+    match run_script(session, &script, output, &mut imported, false) {
+        true => Ok(ExitCode::SUCCESS),
+        false => Ok(ExitCode::FAILURE),
+    }
+}
+
+/// Runs a script, returning whether every statement in it, and in the scripts it
+/// imports, was answered.
+///
+/// An imported script is run quietly, printing only its errors, and only the first
+/// time it's imported, so scripts importing each other don't go round forever. One
+/// that can't be read is an error with the import, like any other statement's.
+fn run_script(
+    session: &mut Session,
+    script: &Script,
+    output: Output,
+    imported: &mut HashSet<PathBuf>,
+    quiet: bool,
+) -> bool {
+    let mut answered = true;
+
+    for statement in script.statements() {
+        let name = match statement.import() {
+            Some(Ok(name)) => name,
+            Some(Err(error)) => {
+                fail(error, &statement, output);
+                answered = false;
+                continue;
+            }
+            None => {
+                answered &= answer_statement(session, &statement, output, quiet);
+                continue;
+            }
+        };
+
+        let path = script.resolve(name);
+        let loaded = fs::canonicalize(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|file| match imported.insert(file) {
+                true => Script::load(&path).map(Some),
+                false => Ok(None),
+            });
+
+        match loaded {
+            Ok(Some(imported_script)) => {
+                answered &= run_script(session, &imported_script, output, imported, true);
+            }
+            Ok(None) => {}
+            Err(err) => {
+                let error = ErrorKind::ImportFailed {
+                    path: path.display().to_string(),
+                    reason: err.root_cause().to_string(),
+                    // where the quoted name is, as the only thing in quotes
+                    at: statement.source.find('"').unwrap_or(0),
+                };
+                fail(error, &statement, output);
+                answered = false;
+            }
+        }
+    }
+
+    answered
+}
+
+fn answer_statement(
+    session: &mut Session,
+    statement: &script::Statement,
+    output: Output,
+    quiet: bool,
+) -> bool {
+    if output == Output::Json {
+        let mut report = caldi_core::report(session, statement.source);
+        let answered = report.get("error").is_none();
+        if !(quiet && answered) {
+            locate(&mut report, statement);
+            println!("{report}");
+        }
+
+        return answered;
+    }
+
+    match session.eval(statement.source) {
+        Ok(answer) => {
+            if !quiet {
+                println!("{answer}");
+            }
+            true
+        }
+        Err(error) => {
+            fail(error, statement, output);
+            false
+        }
+    }
+}
+
+fn fail(error: ErrorKind, statement: &script::Statement, output: Output) {
+    match output {
+        Output::Text => eprintln!("{}", render_script_error(error, statement)),
+        Output::Json => {
+            let mut report = caldi_core::report_error(statement.source, &error);
+            locate(&mut report, statement);
+            println!("{report}");
+        }
+    }
+}
+
+/// Adds where a statement is in its script to its report.
+fn locate(report: &mut serde_json::Value, statement: &script::Statement) {
+    report["file"] = statement.file.display().to_string().into();
+    report["line"] = statement.line.into();
+    report["column"] = statement.column.into();
+}
+
+/// Answers what's piped in line by line, as [`eval`] does, as soon as each line
+/// comes in.
+pub fn run_stdin(session: &mut Session, output: Output) -> anyhow::Result<ExitCode> {
+    let mut exit_code = ExitCode::SUCCESS;
+    for line in io::stdin().lock().lines() {
+        let line = line.context("failed to read from stdin")?;
+        if !answer(session, &line, output) {
+            exit_code = ExitCode::FAILURE;
+        }
+    }
+
+    Ok(exit_code)
+}
+
+/// Prints the answer to `source`, or its error, returning whether there was an
+/// answer. Blank lines are skipped.
+///
+/// As JSON, errors are printed along with everything else, a line per input.
+fn answer(session: &mut Session, source: &str, output: Output) -> bool {
+    if source.trim().is_empty() {
+        return true;
+    }
+
+    if output == Output::Json {
+        let report = caldi_core::report(session, source);
+        println!("{report}");
+        return report.get("error").is_none();
+    }
+
+    match session.eval(source) {
+        Ok(answer) => {
+            println!("{answer}");
+            true
+        }
+        Err(error) => {
+            eprintln!("{}", render_error(error, source));
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process};
+
+    use super::*;
+
+    /// A directory of its own for a test's scripts.
+    fn scripts_dir(test: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("caldi-{test}-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn run(session: &mut Session, script: &Script) -> bool {
+        run_script(session, script, Output::Text, &mut HashSet::new(), false)
+    }
+
+    #[test]
+    fn eval_answers_everything_despite_errors() {
+        let mut session = Session::default();
+        let sources = ["rate = 3", "", "rate * 2"];
+        assert!(eval(&mut session, sources, Output::Text) == ExitCode::SUCCESS);
+
+        let sources = ["rate = rate + 1", "1 +", "rate = rate + 1"];
+        assert!(eval(&mut session, sources, Output::Json) == ExitCode::FAILURE);
+        assert_eq!(session.eval("rate").unwrap(), "5");
+    }
+
+    #[test]
+    fn scripts_run_what_they_import() {
+        let dir = scripts_dir("imports");
+        fs::write(dir.join("rates.cd"), "rate = 3\n").unwrap();
+
+        let mut session = Session::default();
+        let script = Script::new(
+            dir.join("main.cd"),
+            "import \"rates.cd\"\nimport \"rates.cd\"\ntotal = rate * 2\n".to_string(),
+        );
+        assert!(run(&mut session, &script));
+        assert_eq!(session.eval("total").unwrap(), "6");
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn failed_statements_fail_the_script() {
+        let mut session = Session::default();
+        let script = Script::new(
+            PathBuf::from("main.cd"),
+            "rate = 3\nrate +\nrate = rate + 1\n".to_string(),
+        );
+        assert!(!run(&mut session, &script));
+        assert_eq!(session.eval("rate").unwrap(), "4");
+    }
+
+    #[test]
+    fn imports_that_cant_be_read_fail_the_script() {
+        let dir = scripts_dir("missing-import");
+
+        let mut session = Session::default();
+        let script = Script::new(
+            dir.join("main.cd"),
+            "import \"missing.cd\"\nrate = 3\n".to_string(),
+        );
+        assert!(!run(&mut session, &script));
+        assert_eq!(session.eval("rate").unwrap(), "3");
+
+        let missing = run_file(&mut session, &dir.join("missing.cd"), Output::Text);
+        assert!(missing.is_err());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
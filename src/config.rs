@@ -0,0 +1,283 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use caldi_core::Settings;
+use serde::{Deserialize, Serialize};
+
+use crate::dirs;
+
+/// Everything that can be set in `config.toml`, in caldi's config directory, each
+/// part of which can be left out for its defaults. Flags given on the command line
+/// win over it.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub assistant: Assistant,
+    pub audio: Audio,
+    pub vad: Vad,
+    pub whisper: Whisper,
+    pub tts: Tts,
+    pub notification: Notification,
+    /// How answers are worked out and written, for sessions that don't have their
+    /// own settings saved.
+    pub numbers: Settings,
+    pub locale: Locale,
+    pub repl: Repl,
+    pub log: Log,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Assistant {
+    /// What the assistant responds to, as in "Hey, Caldi".
+    pub name: String,
+}
+
+impl Default for Assistant {
+    fn default() -> Self {
+        Self {
+            name: "Caldi".to_string(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Audio {
+    /// How much audio is listened to at a time while waiting to be called.
+    pub buffer_seconds: f64,
+}
+
+impl Default for Audio {
+    fn default() -> Self {
+        Self {
+            buffer_seconds: 2.0,
+        }
+    }
+}
+
+/// Voice activity detection, which is how the assistant tells when someone's done
+/// talking.
+#[derive(Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Vad {
+    /// Samples quieter than this are silence.
+    pub silence_threshold: f64,
+}
+
+impl Default for Vad {
+    fn default() -> Self {
+        Self {
+            silence_threshold: 0.01,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Whisper {
+    /// The ggml model file to transcribe with, if it's not given on the command line.
+    pub model: Option<PathBuf>,
+    pub threads: i32,
+    pub language: String,
+    /// What Whisper is told while waiting to be called, where `{name}` is the
+    /// assistant's name.
+    pub wake_prompt: String,
+    /// What Whisper is told while listening to a problem.
+    pub command_prompt: String,
+}
+
+impl Default for Whisper {
+    fn default() -> Self {
+        Self {
+            model: None,
+            threads: 1,
+            language: "en".to_string(),
+            wake_prompt: r#"[system] The user will probably say "Hey, {name}", and if they don't then just repeat what they said. [user]"#.to_string(),
+            command_prompt: "[system] Get ready. The user will pose some math problems. \
+                Always transcribe numbers as digits, and never letters, \
+                so, for example, if you hear 'five', write 5, and if you hear 'fifty' write '50', and so on... \
+                [user]"
+                .to_string(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Tts {
+    /// What's said when the assistant starts.
+    pub greeting: String,
+    /// What's said once the assistant's been called, and is listening.
+    pub ready: String,
+    /// How fast to speak, in whatever range the speech backend uses, if not at
+    /// its usual rate.
+    pub rate: Option<f64>,
+}
+
+impl Default for Tts {
+    fn default() -> Self {
+        Self {
+            greeting: "Welcome back!".to_string(),
+            ready: "Ready!".to_string(),
+            rate: None,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Notification {
+    /// Whether to show answers and errors as desktop notifications.
+    pub enabled: bool,
+    pub timeout_ms: u32,
+}
+
+impl Default for Notification {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            timeout_ms: 6000,
+        }
+    }
+}
+
+/// How numbers are written in what Whisper transcribes.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Locale {
+    pub decimal_separator: char,
+    pub thousands_separator: char,
+}
+
+impl Default for Locale {
+    fn default() -> Self {
+        Self {
+            decimal_separator: '.',
+            thousands_separator: ',',
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Repl {
+    pub prompt: String,
+    /// How many lines of history to keep between runs.
+    pub history_size: usize,
+}
+
+impl Default for Repl {
+    fn default() -> Self {
+        Self {
+            prompt: ":> ".to_string(),
+            history_size: 1000,
+        }
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Log {
+    /// What to log, as in `RUST_LOG`, e.g. `info,stt=debug`, which `RUST_LOG`
+    /// itself adds to.
+    pub filter: Option<String>,
+    /// A file to also log to, as lines of JSON.
+    pub file: Option<PathBuf>,
+}
+
+impl Config {
+    /// Reads the config file at `path`, or else `config.toml` in caldi's config
+    /// directory, if there is one.
+    pub fn load(path: Option<&Path>) -> anyhow::Result<Self> {
+        let Some(path) = path.map(Path::to_path_buf).or_else(default_path) else {
+            return Ok(Self::default());
+        };
+
+        let contents = fs::read_to_string(&path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+
+        toml::from_str(&contents).with_context(|| format!("failed to parse {}", path.display()))
+    }
+}
+
+/// Where the config file is, if there is one.
+pub fn default_path() -> Option<PathBuf> {
+    dirs::config_dir()
+        .map(|dir| dir.join("config.toml"))
+        .filter(|path| path.exists())
+}
+
+#[cfg(test)]
+mod tests {
+    use caldi_core::{Format, Session};
+    use clap::Parser;
+
+    use super::Config;
+    use crate::{repl, CLi};
+
+    const CONFIG: &str = r#"
+        [numbers]
+        format = "hex"
+        precision = 2
+        max_iterations = 500
+
+        [locale]
+        decimal_separator = ","
+        thousands_separator = "."
+
+        [log]
+        filter = "warn,stt=debug"
+    "#;
+
+    #[test]
+    fn config_files_are_read() {
+        let config: Config = toml::from_str(CONFIG).unwrap();
+
+        assert_eq!(config.numbers.format, Format::Hex);
+        assert_eq!(config.numbers.precision, Some(2));
+        assert_eq!(config.numbers.max_iterations, 500);
+        assert!(!config.numbers.complex);
+        assert_eq!(config.locale.decimal_separator, ',');
+        assert_eq!(config.locale.thousands_separator, '.');
+        assert_eq!(config.log.filter.as_deref(), Some("warn,stt=debug"));
+        // what's left out is left as it is by default
+        assert_eq!(config.assistant.name, "Caldi");
+        assert_eq!(config.repl.prompt, ":> ");
+    }
+
+    #[test]
+    fn unknown_keys_are_rejected() {
+        assert!(toml::from_str::<Config>("colour = true").is_err());
+        assert!(toml::from_str::<Config>("[numbers]\nformatting = \"hex\"").is_err());
+        assert!(toml::from_str::<Config>("[audio]\nbuffer_seconds = 1.0\nbuffer = 2").is_err());
+    }
+
+    /// Settings are taken from the config, then changed by the REPL's commands,
+    /// with any flags given winning over both.
+    #[test]
+    fn flags_win_over_commands_which_win_over_the_config() {
+        let cli = CLi::parse_from(["caldi", "--precision", "4"]);
+        let mut config: Config = toml::from_str(CONFIG).unwrap();
+        cli.apply(&mut config);
+
+        let mut session = Session::default();
+        session.settings = config.numbers.clone();
+        assert_eq!(session.settings.format, Format::Hex);
+        assert_eq!(session.settings.precision, Some(4));
+        assert_eq!(session.settings.max_iterations, 500);
+
+        repl::run_command("format sci", &mut session, None).unwrap();
+        repl::run_command("precision off", &mut session, None).unwrap();
+        assert_eq!(session.settings.format, Format::Scientific);
+        assert_eq!(session.settings.precision, None);
+
+        cli.apply_to_settings(&mut session.settings);
+        assert_eq!(session.settings.format, Format::Scientific);
+        assert_eq!(session.settings.precision, Some(4));
+        assert_eq!(session.settings.max_iterations, 500);
+    }
+}
@@ -0,0 +1,252 @@
+use std::{
+    env,
+    fs::{File, OpenOptions},
+    io::Write,
+    sync::Mutex,
+};
+
+use anyhow::Context;
+use log::{LevelFilter, Log, Metadata, Record};
+use serde_json::json;
+
+use crate::config;
+
+/// Logs to stderr, and to a file of JSON lines if there's one in the config.
+///
+/// What's logged is up to a filter like `RUST_LOG`'s, of comma separated
+/// directives that are either a level for everything, or `target=level` for one
+/// target and those under it, e.g. `warn,stt=debug`. The targets are audio, vad,
+/// stt, calc and tts, and the modules of caldi itself.
+struct Logger {
+    filter: Filter,
+    file: Option<Mutex<File>>,
+}
+
+struct Filter {
+    default: LevelFilter,
+    /// Levels for targets, of which the last one given wins.
+    targets: Vec<(String, LevelFilter)>,
+}
+
+impl Filter {
+    fn parse(&mut self, spec: &str) -> anyhow::Result<()> {
+        for directive in spec.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+            match directive.split_once('=') {
+                Some((target, level)) => {
+                    let level = parse_level(level)?;
+                    self.targets.push((target.trim().to_string(), level));
+                }
+                None => self.default = parse_level(directive)?,
+            }
+        }
+
+        Ok(())
+    }
+
+    fn level(&self, target: &str) -> LevelFilter {
+        self.targets
+            .iter()
+            .filter(|(name, _)| {
+                target == name
+                    || target
+                        .strip_prefix(name.as_str())
+                        .is_some_and(|rest| rest.starts_with("::"))
+            })
+            // of the longest names that match, max_by_key keeps the last given
+            .max_by_key(|(name, _)| name.len())
+            .map_or(self.default, |&(_, level)| level)
+    }
+
+    /// Sets the level for everything from `-v` and `-q`, where being quiet also
+    /// holds every target down to that level, whatever's asked for elsewhere.
+    fn set_verbosity(&mut self, verbosity: i8) {
+        if verbosity == 0 {
+            return;
+        }
+
+        self.default = match verbosity {
+            ..=-2 => LevelFilter::Off,
+            -1 => LevelFilter::Error,
+            1 => LevelFilter::Info,
+            2 => LevelFilter::Debug,
+            _ => LevelFilter::Trace,
+        };
+
+        if verbosity < 0 {
+            let most = self.default;
+            for (_, level) in &mut self.targets {
+                *level = (*level).min(most);
+            }
+        }
+    }
+
+    /// The most that's logged for any target.
+    fn max(&self) -> LevelFilter {
+        self.targets
+            .iter()
+            .map(|&(_, level)| level)
+            .fold(self.default, Ord::max)
+    }
+}
+
+fn parse_level(level: &str) -> anyhow::Result<LevelFilter> {
+    level
+        .trim()
+        .parse()
+        .with_context(|| format!("unknown log level {level}"))
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.filter.level(metadata.target())
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        eprintln!(
+            "[{}] {}: {}",
+            record.level(),
+            record.target(),
+            record.args()
+        );
+
+        if let Some(file) = &self.file {
+            let line = json!({
+                "time": chrono::Local::now().to_rfc3339(),
+                "level": record.level().as_str(),
+                "target": record.target(),
+                "message": record.args().to_string(),
+            });
+
+            let mut file = file.lock().unwrap();
+            // there's nowhere left to say that logging failed
+            let _ = writeln!(file, "{line}");
+        }
+    }
+
+    fn flush(&self) {
+        if let Some(file) = &self.file {
+            let _ = file.lock().unwrap().flush();
+        }
+    }
+}
+
+/// Starts logging, by the config's filter, then `RUST_LOG`'s, with the level for
+/// everything raised by each `-v` (`verbosity` above 0) or lowered by each `-q`
+/// (below 0), to only errors at -1 and nothing at all at -2. Only warnings and
+/// errors are logged otherwise.
+pub fn init(config: &config::Log, verbosity: i8) -> anyhow::Result<()> {
+    let mut filter = Filter {
+        default: LevelFilter::Warn,
+        targets: vec![],
+    };
+
+    if let Some(spec) = &config.filter {
+        filter.parse(spec).context("in the config's log filter")?;
+    }
+    if let Ok(spec) = env::var("RUST_LOG") {
+        filter.parse(&spec).context("in RUST_LOG")?;
+    }
+
+    filter.set_verbosity(verbosity);
+
+    let file = match &config.file {
+        Some(path) => {
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .with_context(|| format!("failed to open the log file {}", path.display()))?;
+            Some(Mutex::new(file))
+        }
+        None => None,
+    };
+
+    log::set_max_level(filter.max());
+    log::set_boxed_logger(Box::new(Logger { filter, file }))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use log::LevelFilter;
+
+    use super::Filter;
+
+    fn filter(spec: &str) -> Filter {
+        let mut filter = Filter {
+            default: LevelFilter::Warn,
+            targets: vec![],
+        };
+        filter.parse(spec).unwrap();
+        filter
+    }
+
+    #[test]
+    fn the_last_directive_for_a_target_wins() {
+        let mut repeated = filter("calc=debug");
+        repeated.parse("calc=error").unwrap();
+        assert_eq!(repeated.level("calc"), LevelFilter::Error);
+
+        assert_eq!(
+            filter("calc=debug,calc=error").level("calc"),
+            LevelFilter::Error
+        );
+        assert_eq!(
+            filter("calc=error,calc=debug").level("calc"),
+            LevelFilter::Debug
+        );
+    }
+
+    #[test]
+    fn the_longest_matching_target_wins() {
+        let filter = filter("info,caldi=debug,caldi::repl=error");
+
+        assert_eq!(filter.level("caldi::repl"), LevelFilter::Error);
+        assert_eq!(filter.level("caldi::repl::helper"), LevelFilter::Error);
+        assert_eq!(filter.level("caldi::batch"), LevelFilter::Debug);
+        assert_eq!(filter.level("stt"), LevelFilter::Info);
+    }
+
+    #[test]
+    fn targets_match_whole_modules() {
+        let filter = filter("calc=trace");
+
+        assert_eq!(filter.level("calc"), LevelFilter::Trace);
+        assert_eq!(filter.level("calc::session"), LevelFilter::Trace);
+        assert_eq!(filter.level("calculator"), LevelFilter::Warn);
+        assert_eq!(filter.level("calc_extra"), LevelFilter::Warn);
+    }
+
+    #[test]
+    fn quiet_holds_every_target_down() {
+        let mut quiet = filter("calc=debug,stt=off");
+        quiet.set_verbosity(-1);
+        assert_eq!(quiet.level("calc"), LevelFilter::Error);
+        assert_eq!(quiet.level("stt"), LevelFilter::Off);
+        assert_eq!(quiet.level("audio"), LevelFilter::Error);
+        assert_eq!(quiet.max(), LevelFilter::Error);
+
+        let mut silent = filter("calc=debug");
+        silent.set_verbosity(-2);
+        assert_eq!(silent.level("calc"), LevelFilter::Off);
+        assert_eq!(silent.max(), LevelFilter::Off);
+
+        // being verbose only raises the level for everything else
+        let mut verbose = filter("calc=error");
+        verbose.set_verbosity(2);
+        assert_eq!(verbose.level("calc"), LevelFilter::Error);
+        assert_eq!(verbose.level("audio"), LevelFilter::Debug);
+    }
+
+    #[test]
+    fn unknown_levels_are_rejected() {
+        let mut filter = filter("");
+        assert!(filter.parse("calc=loud").is_err());
+        assert!(filter.parse("loud").is_err());
+    }
+}
@@ -1,43 +1,170 @@
 use std::{
-    io::{stdin, stdout, Write},
-    path::PathBuf,
+    io::{self, IsTerminal},
+    path::{Path, PathBuf},
+    process::ExitCode,
     sync::{Arc, Condvar, Mutex},
 };
 
-mod calc;
+mod batch;
+mod config;
+mod dirs;
+mod logging;
+mod repl;
 mod stt;
 
 use anyhow::Context;
-use calc::eval;
-use clap::{Args, Parser, Subcommand};
+use caldi_core::{render_error, Format, Rates, Session, Settings};
+use clap::{ArgAction, Args, Parser, Subcommand, ValueEnum};
+use config::Config;
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
 use notify_rust::{Notification, Timeout};
 use ringbuf::{LocalRb, Rb};
 use tts::Tts;
 
-use crate::calc::render_error;
-
 #[derive(Parser)]
 struct CLi {
     #[clap(subcommand)]
     command: Option<Command>,
+
+    /// The config file to read, instead of config.toml in caldi's config directory
+    #[clap(long, global = true)]
+    config: Option<PathBuf>,
+
+    /// A TOML (or JSON) file of exchange rates to convert currencies with.
+    /// Defaults to rates.toml, or rates.json, in caldi's config directory
+    #[clap(long, global = true)]
+    rates: Option<PathBuf>,
+
+    /// Answer with complex numbers where there'd otherwise be no answer,
+    /// e.g. sqrt(-4) is 2i
+    #[clap(long, global = true)]
+    complex: bool,
+
+    /// The most terms a sum or product may have, and the most times the function
+    /// being integrated may be worked out
+    #[clap(long, global = true)]
+    max_iterations: Option<usize>,
+
+    /// Write numbers in answers as auto, fixed, sci, hex, oct or bin
+    #[clap(long, global = true)]
+    format: Option<Format>,
+
+    /// Round answers to this many decimal places
+    #[clap(long, global = true)]
+    precision: Option<usize>,
+
+    /// Seed random numbers, dice and coin flips with this, so they come out the
+    /// same every time
+    #[clap(long, global = true)]
+    seed: Option<u64>,
+
+    /// How to write out answers and errors
+    #[clap(long, global = true, value_enum, default_value_t = Output::Text)]
+    output: Output,
+
+    /// Carry on the saved session of this name, with its variables, functions,
+    /// history and settings, saving it again after every answer
+    #[clap(long, global = true)]
+    session: Option<String>,
+
+    /// How many lines of the REPL's history to keep between runs
+    #[clap(long)]
+    history_size: Option<usize>,
+
+    /// Log more: info with -v, debug with -vv and everything with -vvv
+    #[clap(short, long, global = true, action = ArgAction::Count)]
+    verbose: u8,
+
+    /// Log less: only errors with -q, and nothing at all with -qq
+    #[clap(short, long, global = true, action = ArgAction::Count, conflicts_with = "verbose")]
+    quiet: u8,
+
+    /// Also log to this file, as lines of JSON
+    #[clap(long, global = true)]
+    log_file: Option<PathBuf>,
+}
+
+impl CLi {
+    /// Puts the flags that were given over what the config file says.
+    fn apply(&self, config: &mut Config) {
+        self.apply_to_settings(&mut config.numbers);
+        if let Some(history_size) = self.history_size {
+            config.repl.history_size = history_size;
+        }
+        if let Some(log_file) = &self.log_file {
+            config.log.file = Some(log_file.clone());
+        }
+    }
+
+    /// How much more (or, below 0, less) to log than usual.
+    fn verbosity(&self) -> i8 {
+        self.verbose.min(3) as i8 - self.quiet.min(2) as i8
+    }
+
+    fn apply_to_settings(&self, settings: &mut Settings) {
+        if self.complex {
+            settings.complex = true;
+        }
+        if let Some(max_iterations) = self.max_iterations {
+            settings.max_iterations = max_iterations;
+        }
+        if let Some(format) = self.format {
+            settings.format = format;
+        }
+        if self.precision.is_some() {
+            settings.precision = self.precision;
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum Output {
+    /// As they'd be read out
+    Text,
+    /// A JSON object per input, with its AST, and its answer or error
+    Json,
 }
 
 #[derive(Subcommand)]
 enum Command {
-    Assistant(AssistantInterface),
+    Assistant(Box<AssistantInterface>),
+
+    /// Answer each expression given, printing just the answers
+    Eval {
+        #[clap(required = true)]
+        expressions: Vec<String>,
+    },
+
+    /// Run a .caldi script, printing just the answers
+    Run {
+        file: PathBuf,
+    },
+
+    #[clap(subcommand)]
+    Config(ConfigCommand),
+}
+
+#[derive(Subcommand)]
+enum ConfigCommand {
+    /// Print the configuration in effect, after the config file and flags
+    Show,
 }
 
 #[derive(Args)]
 struct AssistantInterface {
     /// Path to a ggml bin model file.
     /// Follow the quickstart (https://github.com/ggerganov/whisper.cpp/#quick-start)
-    /// from whisper.cpp to get one of these
-    language_model: PathBuf,
+    /// from whisper.cpp to get one of these.
+    /// Defaults to whisper.model in the config file
+    language_model: Option<PathBuf>,
+
+    /// What the assistant responds to.
+    /// Defaults to assistant.name in the config file, or else Caldi
+    #[clap(long = "name")]
+    assistant_name: Option<String>,
 
-    /// What the assistant responds to
-    #[clap(long = "name", default_value = "Caldi")]
-    assistant_name: String,
+    #[clap(skip)]
+    config: Config,
 }
 
 impl AssistantInterface {
@@ -45,23 +172,44 @@ impl AssistantInterface {
     const WHISPER_CHANNEL_COUNT: u16 = 1; // mono because whisper wants it
 
     fn waiting_mode_transcription_prompt(&self) -> String {
-        format!(
-            r#"[system] The user will probably say "Hey, {}", and if they don't then just repeat what they said. [user]"#,
-            self.assistant_name
-        )
+        self.config
+            .whisper
+            .wake_prompt
+            .replace("{name}", &self.config.assistant.name)
     }
 
     fn is_signal_to_start_command(&self, text: &str) -> bool {
         let text = text.trim().to_lowercase();
         if let Some(hey_at) = text.find("hey") {
-            return text[(hey_at + 3)..].contains(&self.assistant_name.to_lowercase());
+            return text[(hey_at + 3)..].contains(&self.config.assistant.name.to_lowercase());
         };
         return false;
     }
 
-    fn handle(self) -> anyhow::Result<()> {
+    fn handle(
+        mut self,
+        config: Config,
+        mut session: Session,
+        saved_as: Option<PathBuf>,
+    ) -> anyhow::Result<()> {
+        // what's given on the command line wins over the config file
+        self.config = config;
+        if let Some(name) = self.assistant_name.take() {
+            self.config.assistant.name = name;
+        }
+        if let Some(model) = self.language_model.take() {
+            self.config.whisper.model = Some(model);
+        }
+
+        let language_model = self.config.whisper.model.clone().context(
+            "expected the path to a ggml model file, on the command line or as whisper.model in the config file",
+        )?;
+
         let mut tts = Tts::default()?;
-        tts.speak("Welcome back!", false)?;
+        if let Some(rate) = self.config.tts.rate {
+            tts.set_rate(rate as f32)?;
+        }
+        tts.speak(self.config.tts.greeting.as_str(), false)?;
 
         let tts = Arc::new(Mutex::new(tts));
         let _tts = Arc::clone(&tts);
@@ -72,7 +220,8 @@ impl AssistantInterface {
             .default_input_device()
             .expect("failed to get input device");
 
-        let audio_input_buffer_size = Self::WHISPER_SAMPLE_RATE * 2; // going for a buffer spanning 2 seconds
+        let audio_input_buffer_size =
+            (Self::WHISPER_SAMPLE_RATE as f64 * self.config.audio.buffer_seconds) as u32;
 
         // We'll try and use the same configuration between streams to keep it simple.
         let config: cpal::StreamConfig = cpal::StreamConfig {
@@ -90,12 +239,20 @@ impl AssistantInterface {
         let _signal = Arc::clone(&signal);
 
         let tr = Arc::new(stt::Transcribe::new(
-            self.language_model
+            language_model
                 .to_str()
                 .expect("received an invalid path for the language_model file"),
+            &self.config.whisper,
         ));
         let _tr = Arc::clone(&tr);
 
+        // what's needed once the input stream has the rest of the config
+        let silence_threshold = self.config.vad.silence_threshold as f32;
+        let ready = self.config.tts.ready.clone();
+        let command_prompt = self.config.whisper.command_prompt.clone();
+        let notification = self.config.notification.clone();
+        let locale = self.config.locale.clone();
+
         let input_stream = device.build_input_stream(
             &config,
             move |data: &[f32], _info| {
@@ -108,18 +265,18 @@ impl AssistantInterface {
                         let (first, second) = waiting_audio.as_slices();
                         let data = &[first, second].concat();
 
-                        if is_silence(data) {
-                            eprintln!("[INFO] silence detected, still waiting");
+                        if is_silence(data, silence_threshold) {
+                            log::info!(target: "vad", "silence detected, still waiting");
                             return;
                         }
 
                         let text = _tr.transcribe(data, &self.waiting_mode_transcription_prompt());
 
-                        eprintln!("[DEBUG] heard and transcribed: {}", text);
+                        log::debug!(target: "stt", "heard and transcribed: {text}");
                         if self.is_signal_to_start_command(&text) {
-                            eprintln!(
-                                "[DEBUG] received signal to start recording command: {}",
-                                &text
+                            log::debug!(
+                                target: "stt",
+                                "received signal to start recording command: {text}"
                             );
 
                             *state = ListenState::PreListening;
@@ -132,11 +289,11 @@ impl AssistantInterface {
                         *state = ListenState::Listening;
                         waiting_audio.clear();
 
-                        eprintln!("[INFO] recording...");
+                        log::info!(target: "audio", "recording...");
 
                         _tts.lock()
                             .unwrap()
-                            .speak("Ready!", false)
+                            .speak(ready.as_str(), false)
                             .expect("failed to speak");
                     }
                     ListenState::Listening => {
@@ -145,15 +302,16 @@ impl AssistantInterface {
                             s.push(sample);
                         }
 
-                        if is_silence(data) && !is_silence(&s) {
-                            eprintln!("[INFO] silence detected after having spoken something");
+                        if is_silence(data, silence_threshold) && !is_silence(&s, silence_threshold)
+                        {
+                            log::info!(target: "vad", "silence detected after having spoken something");
                             *state = ListenState::Transcribing;
                             let (_, cvar) = &*signal;
                             cvar.notify_one();
                         }
                     }
                     ListenState::Transcribing => {
-                        eprintln!("[DEBUG] noop in input_stream, currently transcribing");
+                        log::debug!(target: "audio", "noop in input_stream, currently transcribing");
                     }
                 }
             },
@@ -175,33 +333,33 @@ impl AssistantInterface {
 
             let mut data = speech_audio.lock().unwrap();
 
-            let prompt = r#"
-                [system] 
-                Get ready. The user will pose some math problems. 
-                Always transribe numbers as digits, and never letters, 
-                so, for example, if you hear 'five', write 5, and if you hear 'fifty' write '50', and so on...
-                [user]"#;
-            let text = tr.transcribe(&data, prompt);
-            let answer = eval(&text.replace(',', ""));
+            let text = tr.transcribe(&data, &command_prompt);
+            // errors point into what was worked out, rather than what was heard
+            let problem = normalize_numbers(&text, &locale);
+            let answer = session.eval(&problem);
 
             println!("[problem]: {text}");
 
             match answer {
                 Ok(ans) => {
                     println!("[answer]: {ans}");
-                    notify("Caldi Answer", &format!("{text}\n = {ans}"));
+                    notify(&notification, "Caldi Answer", &format!("{text}\n = {ans}"));
+                    log::debug!(target: "tts", "speaking the answer");
                     tts.lock().unwrap().speak(ans.to_string(), false)?;
                 }
                 Err(error) => {
                     println!("[answer]: {error}");
                     let e_fmtted = error.to_string();
 
-                    notify("Caldi Error", &render_error(error, &text));
+                    notify(&notification, "Caldi Error", &render_error(error, &problem));
 
+                    log::debug!(target: "tts", "speaking the error");
                     tts.lock().unwrap().speak(&e_fmtted, false)?;
                 }
             }
 
+            save_session(&session, saved_as.as_deref());
+
             *state = ListenState::Waiting;
             data.clear();
             input_stream.play()?;
@@ -217,55 +375,179 @@ enum ListenState {
     Transcribing,
 }
 
-fn main() -> Result<(), anyhow::Error> {
+fn main() -> Result<ExitCode, anyhow::Error> {
     let cli = CLi::parse();
 
-    match cli.command {
-        Some(Command::Assistant(a)) => a.handle()?,
-        None => {
-            print!(":> ");
-            stdout().flush()?;
-            for _line in stdin().lines() {
-                let line = _line?;
-
-                let answer = eval(&line);
-
-                answer
-                    .map(|ans| {
-                        println!("{ans}");
-                    })
-                    .unwrap_or_else(|e| {
-                        let e_fmtted = render_error(e, &line);
-                        println!("{}", e_fmtted);
-
-                        notify("Caldi Error", &e_fmtted);
-                    });
-
-                print!(":> ");
-                stdout().flush()?;
+    let mut config = Config::load(cli.config.as_deref())?;
+    cli.apply(&mut config);
+    logging::init(&config.log, cli.verbosity())?;
+
+    let mut session = Session::with_rates(load_rates(cli.rates.clone())?);
+    session.settings = config.numbers.clone();
+
+    let saved_as = cli.session.as_deref().map(session_file).transpose()?;
+    if let Some(path) = saved_as.as_ref().filter(|path| path.exists()) {
+        session.restore(path)?;
+        // flags given this time win over the saved settings
+        cli.apply_to_settings(&mut session.settings);
+    }
+
+    if let Some(seed) = cli.seed {
+        session.seed(seed);
+    }
+
+    let exit_code = match cli.command {
+        Some(Command::Assistant(a)) => {
+            a.handle(config, session, saved_as)?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Config(ConfigCommand::Show)) => {
+            if let Some(path) = cli.config.or_else(config::default_path) {
+                println!("# read from {}\n", path.display());
             }
+            print!("{}", toml::to_string_pretty(&config)?);
+            return Ok(ExitCode::SUCCESS);
         }
+        Some(Command::Eval { expressions }) => batch::eval(
+            &mut session,
+            expressions.iter().map(String::as_str),
+            cli.output,
+        ),
+        Some(Command::Run { file }) => batch::run_file(&mut session, &file, cli.output)?,
+        // something's being piped in, so there's no one to show a prompt to
+        None if !io::stdin().is_terminal() => batch::run_stdin(&mut session, cli.output)?,
+        None => {
+            repl::run(session, &config, cli.output, saved_as)?;
+            return Ok(ExitCode::SUCCESS);
+        }
+    };
+
+    // the REPL and the assistant save as they go, while these save once they're done
+    save_session(&session, saved_as.as_deref());
+
+    Ok(exit_code)
+}
+
+fn load_rates(path: Option<PathBuf>) -> anyhow::Result<Rates> {
+    let path = path.or_else(|| {
+        let dir = dirs::config_dir()?;
+        ["rates.toml", "rates.json"]
+            .into_iter()
+            .map(|file| dir.join(file))
+            .find(|path| path.exists())
+    });
+
+    match path {
+        Some(path) => Rates::load(&path),
+        // converting currencies is the only thing that needs them
+        None => Ok(Rates::default()),
+    }
+}
+
+/// Where the session called `name` is saved.
+fn session_file(name: &str) -> anyhow::Result<PathBuf> {
+    let is_plain = name
+        .chars()
+        .all(|c| c.is_alphanumeric() || c == '-' || c == '_');
+    anyhow::ensure!(
+        is_plain,
+        "a session's name can only have letters, digits, - and _"
+    );
+
+    let dir = dirs::data_dir().context("couldn't find a directory to keep sessions in")?;
+
+    Ok(dir.join("sessions").join(format!("{name}.json")))
+}
+
+/// Saves the session to `path`, if it's to be saved, which isn't worth stopping
+/// over when it can't be.
+fn save_session(session: &Session, path: Option<&Path>) {
+    if let Err(err) = path.map_or(Ok(()), |path| session.save(path)) {
+        log::error!("{err:#}");
     }
+}
 
-    return Ok(());
+fn is_silence(samples: &[f32], threshold: f32) -> bool {
+    !samples.is_empty() && samples.iter().all(|sample| sample.abs() < threshold)
 }
 
-fn is_silence(samples: &[f32]) -> bool {
-    !samples.is_empty() && samples.iter().all(|sample| sample.abs() < 0.01)
+/// Whisper writes large numbers like "1,000", so those commas go, but the ones
+/// separating the items of a list (like "12, 18 and 31") stay. Where the locale
+/// writes "3,5" for three and a half, it becomes "3.5".
+fn normalize_numbers(text: &str, locale: &config::Locale) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let is_digit = |i: usize| chars.get(i).is_some_and(char::is_ascii_digit);
+
+    chars
+        .iter()
+        .enumerate()
+        .filter_map(|(i, &c)| {
+            let after_digit = i > 0 && is_digit(i - 1);
+            let separates_thousands = c == locale.thousands_separator
+                && after_digit
+                && (i + 1..=i + 3).all(is_digit)
+                && !is_digit(i + 4);
+
+            if separates_thousands {
+                None
+            } else if c == locale.decimal_separator && after_digit && is_digit(i + 1) {
+                Some('.')
+            } else {
+                Some(c)
+            }
+        })
+        .collect()
 }
 
 fn err_fn(err: cpal::StreamError) {
-    eprintln!("[ERROR] an error occurred on stream: {}", err);
+    log::error!(target: "audio", "an error occurred on stream: {err}");
 }
 
-fn notify(title: &str, body: &str) {
+fn notify(settings: &config::Notification, title: &str, body: &str) {
+    if !settings.enabled {
+        return;
+    }
+
     if let Err(err) = Notification::new()
         .summary(title)
         .body(body)
-        .timeout(Timeout::Milliseconds(6000))
+        .timeout(Timeout::Milliseconds(settings.timeout_ms))
         .show()
         .context("failed to send desktop notification")
     {
-        eprintln!("[ERROR] {err:#}")
+        log::error!("{err:#}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::normalize_numbers;
+    use crate::config::Locale;
+
+    #[test]
+    fn thousands_separators_go_but_list_commas_stay() {
+        let locale = Locale::default();
+
+        assert_eq!(
+            normalize_numbers("1,000,000 + 2,5", &locale),
+            "1000000 + 2,5"
+        );
+        assert_eq!(normalize_numbers("12, 18 and 31", &locale), "12, 18 and 31");
+        assert_eq!(normalize_numbers("3.25 * 1,200", &locale), "3.25 * 1200");
+    }
+
+    #[test]
+    fn decimal_separators_follow_the_locale() {
+        let locale = Locale {
+            decimal_separator: ',',
+            thousands_separator: '.',
+        };
+
+        assert_eq!(normalize_numbers("3,5 + 1.000", &locale), "3.5 + 1000");
+        assert_eq!(normalize_numbers("1.234.567,89", &locale), "1234567.89");
+        assert_eq!(
+            normalize_numbers("the mean of 4, 5 and 6", &locale),
+            "the mean of 4, 5 and 6"
+        );
     }
 }
@@ -0,0 +1,201 @@
+use std::{borrow::Cow, cell::RefCell, rc::Rc};
+
+use caldi_core::{
+    parse::lexer::{Lexer, TokenKind},
+    Session,
+};
+use rustyline::{
+    completion::Completer,
+    highlight::Highlighter,
+    hint::{Hint, Hinter},
+    validate::Validator,
+    Context, Helper,
+};
+
+use super::COMMANDS;
+
+/// Colors the line being typed, completes names with Tab, and previews its answer.
+pub struct CalcHelper {
+    session: Rc<RefCell<Session>>,
+}
+
+impl CalcHelper {
+    pub fn new(session: Rc<RefCell<Session>>) -> Self {
+        Self { session }
+    }
+}
+
+impl Helper for CalcHelper {}
+
+impl Validator for CalcHelper {}
+
+impl Completer for CalcHelper {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        if let Some(command) = line[..pos].strip_prefix(':') {
+            let candidates = COMMANDS
+                .iter()
+                .filter(|name| name.starts_with(command))
+                .map(|name| format!(":{name}"))
+                .collect();
+
+            return Ok((0, candidates));
+        }
+
+        let start = line[..pos]
+            .rfind(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .map_or(0, |i| {
+                i + line[i..].chars().next().map_or(1, char::len_utf8)
+            });
+        let word = &line[start..pos];
+
+        if word.is_empty() || word.starts_with(|c: char| c.is_ascii_digit()) {
+            return Ok((pos, vec![]));
+        }
+
+        let candidates = self
+            .session
+            .borrow()
+            .names()
+            .into_iter()
+            // names of more than a word can't be told apart from the words before them
+            .filter(|name| name.starts_with(word) && !name.contains(' '))
+            .map(String::from)
+            .collect();
+
+        Ok((start, candidates))
+    }
+}
+
+/// The answer to what's been typed so far, which is only there to be looked at.
+pub struct Preview(String);
+
+impl Hint for Preview {
+    fn display(&self) -> &str {
+        &self.0
+    }
+
+    fn completion(&self) -> Option<&str> {
+        None
+    }
+}
+
+impl Hinter for CalcHelper {
+    type Hint = Preview;
+
+    fn hint(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> Option<Preview> {
+        let source = line.trim();
+        if pos < line.len() || source.is_empty() || source.starts_with(':') {
+            return None;
+        }
+
+        let answer = self.session.borrow().preview(source)?;
+
+        (answer != source).then(|| Preview(format!("  = {answer}")))
+    }
+}
+
+impl Highlighter for CalcHelper {
+    fn highlight<'l>(&self, line: &'l str, _pos: usize) -> Cow<'l, str> {
+        if line.trim_start().starts_with(':') {
+            return Cow::Borrowed(line);
+        }
+
+        let mut highlighted = String::with_capacity(line.len());
+        let mut end = 0;
+
+        for token in Lexer::new(line).tokenize() {
+            if token.kind == TokenKind::Eof || token.start < end {
+                continue;
+            }
+
+            highlighted.push_str(&line[end..token.start]);
+            match color(token.kind) {
+                Some(color) => highlighted.push_str(&format!("\x1b[{color}m{}\x1b[0m", token.text)),
+                None => highlighted.push_str(token.text),
+            }
+            end = token.start + token.text.len();
+        }
+
+        highlighted.push_str(&line[end..]);
+
+        Cow::Owned(highlighted)
+    }
+
+    fn highlight_hint<'h>(&self, hint: &'h str) -> Cow<'h, str> {
+        Cow::Owned(format!("\x1b[2m{hint}\x1b[0m"))
+    }
+
+    fn highlight_char(&self, _line: &str, _pos: usize, _forced: bool) -> bool {
+        // any character can change how the rest of the line is read
+        true
+    }
+}
+
+/// The ANSI color code of a kind of token, if it's colored at all.
+fn color(kind: TokenKind) -> Option<&'static str> {
+    let color = match kind {
+        // yellow
+        TokenKind::Integer | TokenKind::Float | TokenKind::Dice | TokenKind::CurrencySign => "33",
+        // blue
+        TokenKind::Ident => "34",
+        // red and bold
+        TokenKind::Illegal => "1;31",
+        // cyan
+        TokenKind::Minus
+        | TokenKind::Times
+        | TokenKind::ElementTimes
+        | TokenKind::Over
+        | TokenKind::Plus
+        | TokenKind::BitAnd
+        | TokenKind::BitOr
+        | TokenKind::Xor
+        | TokenKind::Not
+        | TokenKind::ShiftLeft
+        | TokenKind::ShiftRight
+        | TokenKind::Less
+        | TokenKind::Greater
+        | TokenKind::LessEqual
+        | TokenKind::GreaterEqual
+        | TokenKind::Equal
+        | TokenKind::NotEqual
+        | TokenKind::Power
+        | TokenKind::Assign
+        | TokenKind::In
+        | TokenKind::Squared
+        | TokenKind::Cubed => "36",
+        // magenta
+        TokenKind::If
+        | TokenKind::Then
+        | TokenKind::Else
+        | TokenKind::Of
+        | TokenKind::And
+        | TokenKind::Define
+        | TokenKind::Numbers
+        | TokenKind::From
+        | TokenKind::HowMany
+        | TokenKind::Between
+        | TokenKind::Meridiem
+        | TokenKind::Solve
+        | TokenKind::For
+        | TokenKind::Makes
+        | TokenKind::Derivative
+        | TokenKind::WithRespectTo
+        | TokenKind::CoinFlip => "35",
+        TokenKind::LParen
+        | TokenKind::RParen
+        | TokenKind::Comma
+        | TokenKind::LBracket
+        | TokenKind::RBracket
+        | TokenKind::Colon
+        | TokenKind::Eof => return None,
+    };
+
+    Some(color)
+}
@@ -0,0 +1,254 @@
+use std::{
+    cell::RefCell,
+    fs,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use anyhow::Context;
+use caldi_core::{render_error, Format, Session};
+use rustyline::{error::ReadlineError, history::FileHistory, Editor};
+
+use crate::{config::Config, dirs, notify, save_session, session_file, Output};
+
+use self::helper::CalcHelper;
+
+mod helper;
+
+/// The names of the commands, for completing them.
+const COMMANDS: [&str; 10] = [
+    "help",
+    "vars",
+    "funcs",
+    "clear",
+    "history",
+    "format",
+    "precision",
+    "save",
+    "load",
+    "quit",
+];
+
+const HELP: &str = "\
+Operators, and the ways they can be said:
+  +          plus
+  -          minus, negative
+  *          times, x, multiplied by
+  /          over, divided by
+  .*         elementwise times
+  ^          to the power of, raised to, squared, cubed
+  &          bitwise and
+  |          bitwise or
+  xor        bitwise xor, exclusive or
+  ~          not, bitwise not
+  <<         shifted left by, left shift
+  >>         shifted right by, right shift
+  <  >       less than, greater than, more than
+  <= >=      at most, at least, less/greater than or equal to
+  == !=      equals, is equal to, not equal to, does not equal
+  in         to, into (converts units, currencies and times)
+  if a then b else c
+  2 y, 3(4 + 5), 2pi   side by side, operands are multiplied
+
+Definitions:
+  x = 5, let x be 5
+  f(x) = x^2, define f of x as x squared
+
+Commands:
+  :help               this
+  :vars               the variables defined so far
+  :funcs              the functions defined so far
+  :clear              forget every variable and function
+  :history            the inputs entered so far, and their answers
+  :format [mode]      write numbers as auto, fixed, sci, hex, oct or bin
+  :precision [N|off]  round answers to N decimal places
+  :save [name]        save the variables, functions, history and settings
+  :load [name]        bring back a saved session
+  :quit               leave, as Ctrl-D does";
+
+/// Whether to keep reading lines after a command.
+pub enum Flow {
+    Continue,
+    Quit,
+}
+
+/// Reads expressions line by line and answers each, with the usual line editing,
+/// history recall and Ctrl-R search. The history is kept in caldi's data directory
+/// between runs, up to as many lines of it as the config says.
+///
+/// As it's typed, the line is colored, names can be completed with Tab, and its
+/// answer is previewed after it.
+///
+/// If the session is `saved_as` a file, it's saved there after every answer.
+pub fn run(
+    session: Session,
+    config: &Config,
+    output: Output,
+    saved_as: Option<PathBuf>,
+) -> anyhow::Result<()> {
+    let editor_config = rustyline::Config::builder()
+        .max_history_size(config.repl.history_size)?
+        .auto_add_history(true)
+        .build();
+    let session = Rc::new(RefCell::new(session));
+    let mut editor = Editor::<CalcHelper, FileHistory>::with_config(editor_config)?;
+    editor.set_helper(Some(CalcHelper::new(Rc::clone(&session))));
+
+    let history = history_file();
+    if let Some(history) = history.as_ref().filter(|path| path.exists()) {
+        if let Err(err) = editor.load_history(history) {
+            log::error!("failed to load history from {}: {err}", history.display());
+        }
+    }
+
+    loop {
+        let line = match editor.readline(&config.repl.prompt) {
+            Ok(line) => line,
+            // Ctrl-C lets go of the line being typed, Ctrl-D leaves
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(err) => return Err(err.into()),
+        };
+
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        // commands are dealt with here, so they never reach the calculator
+        if let Some(command) = line.trim().strip_prefix(':') {
+            match run_command(command, &mut session.borrow_mut(), saved_as.as_deref()) {
+                Ok(Flow::Continue) => continue,
+                Ok(Flow::Quit) => break,
+                Err(err) => {
+                    println!("{err:#}");
+                    continue;
+                }
+            }
+        }
+
+        if output == Output::Json {
+            println!("{}", caldi_core::report(&mut session.borrow_mut(), &line));
+        } else {
+            let answer = session.borrow_mut().eval(&line);
+            match answer {
+                Ok(answer) => println!("{answer}"),
+                Err(error) => {
+                    let e_fmtted = render_error(error, &line);
+                    println!("{}", e_fmtted);
+
+                    notify(&config.notification, "Caldi Error", &e_fmtted);
+                }
+            }
+        }
+
+        save_session(&session.borrow(), saved_as.as_deref());
+    }
+
+    // the settings might've changed since the last answer
+    save_session(&session.borrow(), saved_as.as_deref());
+
+    if let Some(history) = history {
+        let saved = history
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .map_err(anyhow::Error::from)
+            .and_then(|_| Ok(editor.save_history(&history)?));
+
+        if let Err(err) = saved {
+            log::error!("failed to save history to {}: {err}", history.display());
+        }
+    }
+
+    Ok(())
+}
+
+/// Runs a command, where `saved_as` is the session's file, if it's got one, which
+/// `:save` and `:load` go to when they're not given a name.
+pub fn run_command(
+    command: &str,
+    session: &mut Session,
+    saved_as: Option<&Path>,
+) -> anyhow::Result<Flow> {
+    let (name, argument) = match command.split_once(char::is_whitespace) {
+        Some((name, argument)) => (name, Some(argument.trim()).filter(|a| !a.is_empty())),
+        None => (command, None),
+    };
+
+    match name {
+        "help" | "h" | "?" => println!("{HELP}"),
+        "vars" => {
+            for (name, value) in session.variables() {
+                println!("{name} = {value}");
+            }
+        }
+        "funcs" => {
+            for function in session.functions() {
+                println!("{function}");
+            }
+        }
+        "clear" => {
+            session.clear();
+            println!("cleared every variable and function");
+        }
+        "history" => {
+            for (i, exchange) in session.history().iter().enumerate() {
+                println!("{:>4}  {}", i + 1, exchange.input);
+                match &exchange.answer {
+                    Ok(answer) => println!("      {answer}"),
+                    Err(error) => println!("      {error}"),
+                }
+            }
+        }
+        "format" => {
+            if let Some(format) = argument {
+                session.settings.format = format.parse::<Format>().map_err(anyhow::Error::msg)?;
+            }
+            println!("format: {}", session.settings.format);
+        }
+        "precision" => {
+            match argument {
+                Some("off" | "auto") => session.settings.precision = None,
+                Some(digits) => {
+                    let digits = digits
+                        .parse()
+                        .with_context(|| format!("expected a number of digits, not {digits}"))?;
+                    session.settings.precision = Some(digits);
+                }
+                None => {}
+            }
+
+            match session.settings.precision {
+                Some(digits) => println!("precision: {digits} decimal places"),
+                None => println!("precision: off"),
+            }
+        }
+        "save" => {
+            let path = session_path(argument, saved_as)?;
+            session.save(&path)?;
+            println!("saved to {}", path.display());
+        }
+        "load" => {
+            let path = session_path(argument, saved_as)?;
+            session.restore(&path)?;
+            println!("loaded {}", path.display());
+        }
+        "quit" | "q" | "exit" => return Ok(Flow::Quit),
+        _ => anyhow::bail!("unknown command :{name}, see :help"),
+    }
+
+    Ok(Flow::Continue)
+}
+
+/// The file of the session called `name`, or else the one the session is saved
+/// as, or else the one called "default".
+fn session_path(name: Option<&str>, saved_as: Option<&Path>) -> anyhow::Result<PathBuf> {
+    match (name, saved_as) {
+        (Some(name), _) => session_file(name),
+        (None, Some(path)) => Ok(path.to_path_buf()),
+        (None, None) => session_file("default"),
+    }
+}
+
+fn history_file() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("history.txt"))
+}
@@ -1,16 +1,24 @@
 use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
 
+use crate::config::Whisper;
+
 pub struct Transcribe {
     ctx: WhisperContext,
+    threads: i32,
+    language: String,
 }
 
 impl Transcribe {
-    pub fn new(path_to_model: &str) -> Self {
+    pub fn new(path_to_model: &str, config: &Whisper) -> Self {
         let ctx =
             WhisperContext::new_with_params(path_to_model, WhisperContextParameters::default())
                 .expect("failed to load model");
 
-        Self { ctx }
+        Self {
+            ctx,
+            threads: config.threads,
+            language: config.language.clone(),
+        }
     }
 
     pub fn transcribe(&self, audio_data: &[f32], prompt: &str) -> String {
@@ -21,7 +29,8 @@ impl Transcribe {
         let tokens = &ctx.tokenize(prompt, prompt.len()).unwrap();
         params.set_tokens(tokens);
 
-        params.set_n_threads(1);
+        params.set_n_threads(self.threads);
+        params.set_language(Some(&self.language));
         params.set_print_progress(false);
         params.set_print_realtime(false);
         params.set_print_timestamps(false);